@@ -0,0 +1,39 @@
+//Annotates the games read from the standard input, one move string per line, and writes them as
+//game records. The first moves are skipped as the opening is slow to solve:
+//
+//  cargo run --release --example annotate -- [first annotated move, 12 by default] < games.txt
+
+use quatrocious::annotate::{annotate, to_record};
+use quatrocious::Solver;
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let from = match std::env::args().nth(1).map(|argument| argument.parse()) {
+        None => 12,
+        Some(Ok(from)) => from,
+        Some(Err(_)) => {
+            eprintln!("usage: annotate [first annotated move]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut solver = Solver::new();
+    for (line_number, line) in io::stdin().lock().lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+        let moves = line.trim();
+        if moves.is_empty() {
+            continue;
+        }
+        match annotate(&mut solver, moves, from).and_then(|annotations| to_record(moves, &annotations)) {
+            Ok(record) => println!("{}", record),
+            Err(error) => eprintln!("line {}: {}", line_number + 1, error),
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,149 @@
+use crate::board::Board;
+use crate::error::Error;
+use crate::game::Game;
+use crate::notation;
+use crate::record::GameRecord;
+use crate::score::Score;
+use crate::Solver;
+use std::fmt;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Verdict {
+    Best,
+    Inaccuracy, //Same result, but a slower win or a quicker loss
+    Blunder,    //Changes the result: a win into a draw or a loss, or a draw into a loss
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Best => write!(f, "Best"),
+            Verdict::Inaccuracy => write!(f, "Inaccuracy"),
+            Verdict::Blunder => write!(f, "Blunder"),
+        }
+    }
+}
+
+//Scores are for the player who plays the move
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AnnotatedMove {
+    pub index: usize, //Number of moves before this one
+    pub column: u32,
+    pub score: Score,
+    pub best_score: Score,
+    pub best_moves: Vec<u32>,
+    pub verdict: Verdict,
+}
+
+//Solves the positions of a move string, from the move at index from (the opening is slow to solve)
+pub fn annotate(solver: &mut Solver, moves: &str, from: usize) -> Result<Vec<AnnotatedMove>, Error> {
+    let (_, columns) = notation::parse_digits(moves)?;
+    let mut board = Board::empty();
+    let mut result = Vec::new();
+    for (index, &column) in columns.iter().enumerate() {
+        if index >= from {
+            let scores = solver.analyze(board).scores;
+            let best_score = scores.iter().flatten().copied().max().unwrap(); //The move is legal
+            let best_moves = (0..Board::WIDTH)
+                .filter(|&column| scores[column as usize] == Some(best_score))
+                .collect();
+            let score = scores[column as usize].unwrap();
+            let verdict = if score == best_score {
+                Verdict::Best
+            } else if score.outcome() == best_score.outcome() {
+                Verdict::Inaccuracy
+            } else {
+                Verdict::Blunder
+            };
+            result.push(AnnotatedMove {
+                index,
+                column,
+                score,
+                best_score,
+                best_moves,
+                verdict,
+            });
+        }
+        board = board.make_move(column).unwrap(); //Already checked while parsing
+    }
+    Ok(result)
+}
+
+//Scores every annotated move, with a comment naming the best moves after a mistake
+pub fn to_record(moves: &str, annotations: &[AnnotatedMove]) -> Result<GameRecord, Error> {
+    let mut record = GameRecord::from_game(&Game::from_string(moves)?);
+    for annotation in annotations {
+        let record_move = &mut record.moves[annotation.index];
+        record_move.score = Some(annotation.score);
+        if annotation.verdict != Verdict::Best {
+            let best_moves: Vec<String> = annotation
+                .best_moves
+                .iter()
+                .map(|&column| notation::column_to_digit(column).to_string())
+                .collect();
+            record_move.comment = Some(format!(
+                "{}, best {} ({})",
+                annotation.verdict,
+                best_moves.join(" or "),
+                annotation.best_score
+            ));
+        }
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::GameResult;
+    use crate::tests::test_moves;
+
+    #[test]
+    fn verdicts() {
+        let mut solver = Solver::new();
+        for (moves, score) in test_moves(include_str!("./test_sets/Test_L3_R1")).take(20) {
+            let board = Board::from_string(moves).unwrap();
+            let scores = solver.analyze(board).scores;
+            for column in (0..Board::WIDTH).filter(|&column| board.can_play(column)) {
+                let game = format!("{}{}", moves, notation::column_to_digit(column));
+                let annotations = annotate(&mut solver, &game, moves.len()).unwrap();
+                assert_eq!(annotations.len(), 1);
+                let annotation = &annotations[0];
+                assert_eq!(annotation.index, moves.len());
+                assert_eq!(annotation.best_score, score);
+                assert_eq!(Some(annotation.score), scores[column as usize]);
+                assert!(annotation.best_moves.contains(&column) == (annotation.score == score));
+                let expected = if annotation.score == score {
+                    Verdict::Best
+                } else if annotation.score.outcome() == score.outcome() {
+                    Verdict::Inaccuracy
+                } else {
+                    Verdict::Blunder
+                };
+                assert_eq!(annotation.verdict, expected, "{}", game);
+            }
+        }
+    }
+
+    #[test]
+    fn record() {
+        //White wins with 2 or 6, and loses with 5
+        let moves = "74223417356477411661335734732425665";
+        let mut solver = Solver::new();
+        assert!(annotate(&mut solver, "44a", 0).is_err());
+        let annotations = annotate(&mut solver, moves, 34).unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].verdict, Verdict::Blunder);
+        assert_eq!(annotations[0].best_moves, vec![1, 5]);
+
+        let record = to_record(moves, &annotations).unwrap();
+        assert_eq!(record.moves[33].score, None);
+        assert_eq!(record.moves[34].score, Some(Score::new(-4)));
+        assert_eq!(
+            record.moves[34].comment.as_deref(),
+            Some("Blunder, best 2 or 6 (1)")
+        );
+        assert_eq!(record.result, GameResult::Unfinished);
+        assert_eq!(GameRecord::parse(&record.to_string()).unwrap(), record);
+    }
+}
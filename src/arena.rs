@@ -0,0 +1,245 @@
+use crate::board::{Board, Player};
+use crate::Engine;
+use std::fmt;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MatchGame {
+    pub opening: Board,
+    pub first_engine: Player, //The colour played by the first engine
+    pub moves: Vec<u32>,      //Played from the opening
+    pub winner: Option<Player>,
+}
+
+impl MatchGame {
+    //1 for a win of the first engine, 0.5 for a draw
+    pub fn first_engine_score(&self) -> f64 {
+        match self.winner {
+            Some(player) if player == self.first_engine => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+//Results from the point of view of the first engine
+#[derive(Clone, Debug, Default)]
+pub struct MatchResult {
+    pub games: Vec<MatchGame>,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+//Elo difference giving this expected score
+fn elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+impl MatchResult {
+    pub fn add(&mut self, game: MatchGame) {
+        match game.winner {
+            Some(player) if player == game.first_engine => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+        self.games.push(game);
+    }
+
+    pub fn game_count(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    //Between 0 and 1, draws count half. None without games
+    pub fn score(&self) -> Option<f64> {
+        match self.game_count() {
+            0 => None,
+            count => Some((self.wins as f64 + self.draws as f64 / 2.0) / count as f64),
+        }
+    }
+
+    //Scores of 0 and 1 give infinite Elo differences: they are moved by half a game
+    fn clamp_score(&self, score: f64) -> f64 {
+        let correction = 0.5 / self.game_count() as f64;
+        score.clamp(correction, 1.0 - correction)
+    }
+
+    //Estimated strength of the first engine over the second one
+    pub fn elo_difference(&self) -> Option<f64> {
+        Some(elo(self.clamp_score(self.score()?)))
+    }
+
+    //Half width of the 95% confidence interval of elo_difference
+    pub fn elo_margin(&self) -> Option<f64> {
+        let count = self.game_count() as f64;
+        let score = self.clamp_score(self.score()?);
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / count;
+        let deviation = 1.96 * (variance / count).sqrt();
+        let low = self.clamp_score(score - deviation);
+        let high = self.clamp_score(score + deviation);
+        Some((elo(high) - elo(low)) / 2.0)
+    }
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.score(), self.elo_difference(), self.elo_margin()) {
+            (Some(score), Some(elo_difference), Some(elo_margin)) => write!(
+                f,
+                "+{} ={} -{}, score {:.1}%, Elo {:+.0} ± {:.0}",
+                self.wins,
+                self.draws,
+                self.losses,
+                100.0 * score,
+                elo_difference,
+                elo_margin
+            ),
+            _ => write!(f, "no games"),
+        }
+    }
+}
+
+//Plays until the end of the game, the engines must play a legal move when the game is not over
+pub fn play_game<W: Engine + ?Sized, B: Engine + ?Sized>(
+    white: &mut W,
+    black: &mut B,
+    opening: Board,
+) -> (Vec<u32>, Option<Player>) {
+    let mut board = opening;
+    let mut moves = Vec::new();
+    while !board.game_over() {
+        let column = match board.player_to_play() {
+            Player::White => white.best_move(board),
+            Player::Black => black.best_move(board),
+        }
+        .expect("no move while the game is not over");
+        board = board.make_move(column).expect("illegal move");
+        moves.push(column);
+    }
+    let winner = if board.is_victory() {
+        Some(board.player_to_play().opponent()) //The last player won
+    } else {
+        None
+    };
+    (moves, winner)
+}
+
+//Two games per opening, with each engine playing White once
+pub fn play_match<F: Engine + ?Sized, S: Engine + ?Sized>(
+    first: &mut F,
+    second: &mut S,
+    openings: &[Board],
+) -> MatchResult {
+    let mut result = MatchResult::default();
+    for &opening in openings {
+        for first_engine in [Player::White, Player::Black] {
+            let (moves, winner) = match first_engine {
+                Player::White => play_game(first, second, opening),
+                Player::Black => play_game(second, first, opening),
+            };
+            result.add(MatchGame {
+                opening,
+                first_engine,
+                moves,
+                winner,
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::opening_boards;
+    use crate::heuristic::HeuristicEngine;
+    use crate::score::Outcome;
+    use crate::tests::test_positions;
+    use crate::Solver;
+
+    #[test]
+    fn heuristic_depths() {
+        let openings = opening_boards(2);
+        let result = play_match(&mut HeuristicEngine::new(4), &mut HeuristicEngine::new(0), &openings);
+        assert_eq!(result.game_count() as usize, 2 * openings.len());
+        assert_eq!(result.games.len(), 2 * openings.len());
+        assert!(result.score().unwrap() > 0.5, "{}", result);
+        for game in &result.games {
+            let mut board = game.opening;
+            for &column in &game.moves {
+                board = board.make_move(column).unwrap();
+            }
+            assert!(board.game_over());
+            assert_eq!(board.is_victory(), game.winner.is_some());
+        }
+        assert_eq!(result.games[0].first_engine, Player::White);
+        assert_eq!(result.games[1].first_engine, Player::Black);
+    }
+
+    #[test]
+    fn perfect_play_never_loses_a_won_position() {
+        let mut solver = Solver::new();
+        let mut heuristic = HeuristicEngine::new(2);
+        for (board, score) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(10) {
+            let result = play_match(&mut solver, &mut heuristic, &[board]);
+            let game = result
+                .games
+                .iter()
+                .find(|game| game.first_engine == board.player_to_play())
+                .unwrap();
+            match score.outcome() {
+                Outcome::Win => assert_eq!(game.winner, Some(game.first_engine)),
+                Outcome::Draw => assert_ne!(game.winner, Some(game.first_engine.opponent())),
+                Outcome::Loss => {}
+            }
+        }
+    }
+
+    #[test]
+    fn elo() {
+        let mut result = MatchResult {
+            wins: 6,
+            draws: 2,
+            losses: 2,
+            ..MatchResult::default()
+        };
+        assert!((result.score().unwrap() - 0.7).abs() < 1e-9);
+        assert!((result.elo_difference().unwrap() - 147.2).abs() < 0.1);
+        assert!(result.elo_margin().unwrap() > 0.0);
+        result.wins = 5;
+        result.losses = 3;
+        result.draws = 2;
+        assert!(result.elo_difference().unwrap() > 0.0);
+        result.wins = 3;
+        result.losses = 5;
+        assert!(result.elo_difference().unwrap() < 0.0);
+        assert!(result.to_string().starts_with("+3 =2 -5, score 40.0%"));
+    }
+
+    #[test]
+    fn elo_without_losses_or_games() {
+        let sweep = MatchResult {
+            wins: 4,
+            ..MatchResult::default()
+        };
+        let elo_difference = sweep.elo_difference().unwrap();
+        assert!(elo_difference.is_finite() && elo_difference > 0.0);
+        assert!(sweep.elo_margin().unwrap().is_finite());
+        assert_eq!(sweep.to_string(), "+4 =0 -0, score 100.0%, Elo +338 ± 72");
+
+        let lost = MatchResult {
+            losses: 4,
+            ..MatchResult::default()
+        };
+        assert!((lost.elo_difference().unwrap() + elo_difference).abs() < 1e-9);
+        assert!(lost.to_string().starts_with("+0 =0 -4, score 0.0%, Elo -338"));
+
+        let empty = MatchResult::default();
+        assert_eq!(empty.score(), None);
+        assert_eq!(empty.elo_difference(), None);
+        assert_eq!(empty.elo_margin(), None);
+        assert_eq!(empty.to_string(), "no games");
+    }
+}
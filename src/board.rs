@@ -1,4 +1,11 @@
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+use crate::error::Error;
+use crate::notation;
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum Player {
     White,
     Black,
@@ -13,12 +20,65 @@ impl Player {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Player::White => write!(f, "White"),
+            Player::Black => write!(f, "Black"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum Square {
     Empty,
     Taken(Player),
 }
 
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Square::Empty => write!(f, "."),
+            Square::Taken(Player::White) => write!(f, "x"),
+            Square::Taken(Player::Black) => write!(f, "o"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GridError {
+    FloatingDisc { column: u32, row: u32 }, //The square below this disc is empty
+    WrongDiscCount { white: u32, black: u32 }, //White must have as many discs as Black, or one more
+    BothPlayersWon,
+    WrongWinner(Player),   //This player has four in a row but did not play the last move
+    ImpossibleWin(Player), //No single last move could have completed all of this player's fours
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::FloatingDisc { column, row } => {
+                write!(f, "floating disc at column {}, row {}", column, row)
+            }
+            GridError::WrongDiscCount { white, black } => write!(
+                f,
+                "wrong disc count: {} for White and {} for Black",
+                white, black
+            ),
+            GridError::BothPlayersWon => write!(f, "both players have four in a row"),
+            GridError::WrongWinner(player) => {
+                write!(f, "{} has four in a row but did not play last", player)
+            }
+            GridError::ImpossibleWin(player) => {
+                write!(f, "{}'s fours cannot have been made by one move", player)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Board {
     current_player: u64,
@@ -26,6 +86,9 @@ pub struct Board {
     filled: u32,
 }
 
+//The padded columns used by Board::symmetric must fit in a u64
+const _: () = assert!(Board::WIDTH.next_power_of_two() * (Board::HEIGHT + 1) <= 64);
+
 impl Board {
     pub const WIDTH: u32 = 7;
     pub const HEIGHT: u32 = 6;
@@ -43,11 +106,41 @@ impl Board {
 
     const BOTTOM: u64 = Self::bottom(Self::WIDTH);
     const BOARD_MASK: u64 = Self::BOTTOM * ((1 << Self::HEIGHT) - 1);
+    pub(crate) const ODD_ROWS: u64 = Self::BOTTOM * (0x5555 & ((1 << Self::HEIGHT) - 1)); //Odd rows, counting from 1
+
+    //The mirror works on a power of two of columns (empty ones are added on the right)
+    const MIRROR_COLUMNS: u32 = Self::WIDTH.next_power_of_two();
+
+    //For each swap size (half, quarter...), the columns that move right, including their top bit
+    const fn mirror_masks() -> [u64; 6] {
+        let column_bits: u64 = (1 << (Self::HEIGHT + 1)) - 1;
+        let mut result = [0; 6];
+        let mut stage = 0;
+        let mut half = Self::MIRROR_COLUMNS / 2;
+        while half > 0 {
+            let mut column = 0;
+            while column < Self::MIRROR_COLUMNS {
+                if column & half == 0 {
+                    result[stage] |= column_bits << (column * (Self::HEIGHT + 1));
+                }
+                column += 1;
+            }
+            stage += 1;
+            half /= 2;
+        }
+        result
+    }
+
+    const MIRROR_MASKS: [u64; 6] = Self::mirror_masks();
 
     pub fn column_mask(column: u32) -> u64 {
         ((1 << Self::HEIGHT) - 1) << (column * (Self::HEIGHT + 1))
     }
 
+    pub fn mask_column(move_mask: u64) -> u32 {
+        move_mask.trailing_zeros() / (Self::HEIGHT + 1)
+    }
+
     fn row_mask(row: u32) -> u64 {
         Self::BOTTOM << row
     }
@@ -102,24 +195,82 @@ impl Board {
         }
     }
 
-    pub fn from_string(string: &str) -> Result<Self, String> {
-        let mut board = Self::empty();
-        for character in string.chars() {
-            if board.game_over() {
-                return Err(String::from("Too many moves!"));
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        notation::parse_digits(string).map(|(board, _)| board)
+    }
+
+    pub fn from_grid(
+        grid: &[[Square; Self::WIDTH as usize]; Self::HEIGHT as usize],
+    ) -> Result<Self, GridError> {
+        //The grid is indexed by row (from the bottom), then column
+        let mut white = 0;
+        let mut black = 0;
+        for column in 0..Self::WIDTH {
+            let mut column_filled = true;
+            for row in 0..Self::HEIGHT {
+                match grid[row as usize][column as usize] {
+                    Square::Empty => column_filled = false,
+                    Square::Taken(_) if !column_filled => {
+                        return Err(GridError::FloatingDisc { column, row })
+                    }
+                    Square::Taken(Player::White) => white |= Self::square_mask(column, row),
+                    Square::Taken(Player::Black) => black |= Self::square_mask(column, row),
+                }
+            }
+        }
+
+        let mask = white | black;
+        let (white_count, black_count) = (white.count_ones(), black.count_ones());
+        if white_count != black_count && white_count != black_count + 1 {
+            return Err(GridError::WrongDiscCount {
+                white: white_count,
+                black: black_count,
+            });
+        }
+        let last_player = if white_count == black_count {
+            Player::Black
+        } else {
+            Player::White
+        };
+
+        let (white_won, black_won) = (Self::has_four(white), Self::has_four(black));
+        if white_won && black_won {
+            return Err(GridError::BothPlayersWon);
+        }
+        if white_won || black_won {
+            let (winner, winner_positions) = if white_won {
+                (Player::White, white)
+            } else {
+                (Player::Black, black)
+            };
+            if winner != last_player {
+                return Err(GridError::WrongWinner(winner));
             }
-            let digit = character
-                .to_digit(10)
-                .ok_or(String::from("The character is not a digit!"))?;
-            if !(1..=7).contains(&digit) {
-                return Err(String::from("The digit is not in the range 1..=7"));
+            //The last move is on top of a column, and every four in a row must go through it
+            let mut candidates = mask & !(mask >> 1) & winner_positions;
+            let mut possible = false;
+            while candidates != 0 {
+                let last_move = candidates & candidates.wrapping_neg();
+                if !Self::has_four(winner_positions ^ last_move) {
+                    possible = true;
+                    break;
+                }
+                candidates ^= last_move;
+            }
+            if !possible {
+                return Err(GridError::ImpossibleWin(winner));
             }
-            let column = digit - 1;
-            board = board
-                .make_move(column)
-                .map_err(|_| String::from("Invalid move!"))?;
         }
-        Ok(board)
+
+        Ok(Self {
+            current_player: if last_player == Player::Black {
+                white
+            } else {
+                black
+            },
+            mask,
+            filled: white_count + black_count,
+        })
     }
 
     pub fn player_to_play(self) -> Player {
@@ -155,13 +306,19 @@ impl Board {
         if column >= Self::WIDTH || self.game_over() {
             return false;
         }
-        let top_mask = (1 << Self::HEIGHT - 1) << (column * (Self::HEIGHT + 1));
+        let top_mask = (1 << (Self::HEIGHT - 1)) << (column * (Self::HEIGHT + 1));
         self.mask & top_mask == 0
     }
 
-    pub fn make_move(self, column: u32) -> Result<Board, ()> {
+    pub fn make_move(self, column: u32) -> Result<Board, Error> {
+        if column >= Self::WIDTH {
+            return Err(Error::ColumnOutOfRange { column });
+        }
+        if self.game_over() {
+            return Err(Error::GameOver);
+        }
         if !self.can_play(column) {
-            return Err(());
+            return Err(Error::ColumnFull { column });
         }
         Ok(Self {
             current_player: self.current_player ^ self.mask, //flip all values
@@ -171,19 +328,18 @@ impl Board {
     }
 
     fn symmetric(values: u64) -> u64 {
-        let mut result = 0;
-        for column in 0..Self::WIDTH {
-            let target_column = Self::WIDTH - 1 - column;
-            let selected = values & Self::column_mask(column);
-            if target_column < column { //We shift left on the board: that's a right shift
-                let offset = column - target_column;
-                result |= selected >> (offset * (Self::HEIGHT + 1));
-            } else {
-                let offset = target_column - column;
-                result |= selected << (offset * (Self::HEIGHT + 1));
-            }
+        //Reverse the order of the (padded) columns by swapping halves, then quarters, etc.
+        let mut result = values;
+        let mut stage = 0;
+        let mut half = Self::MIRROR_COLUMNS / 2;
+        while half > 0 {
+            let shift = half * (Self::HEIGHT + 1);
+            let mask = Self::MIRROR_MASKS[stage];
+            result = ((result >> shift) & mask) | ((result & mask) << shift);
+            stage += 1;
+            half /= 2;
         }
-        result
+        result >> ((Self::MIRROR_COLUMNS - Self::WIDTH) * (Self::HEIGHT + 1)) //Drop the padding
     }
 
     pub fn symmetric_board(self) -> Self {
@@ -194,9 +350,7 @@ impl Board {
         }
     }
 
-    pub fn is_victory(self) -> bool {
-        let last_player = self.mask ^ self.current_player;
-
+    pub(crate) fn has_four(last_player: u64) -> bool {
         let horizontal_overlap_1 = last_player & (last_player >> (Self::HEIGHT + 1));
         let horizontal_overlap_2 =
             horizontal_overlap_1 & (horizontal_overlap_1 >> (2 * (Self::HEIGHT + 1)));
@@ -226,6 +380,22 @@ impl Board {
         false
     }
 
+    pub(crate) fn player_squares(self) -> u64 {
+        self.current_player
+    }
+
+    pub(crate) fn opponent_squares(self) -> u64 {
+        self.mask ^ self.current_player
+    }
+
+    pub(crate) fn empty_squares(self) -> u64 {
+        Self::BOARD_MASK & !self.mask
+    }
+
+    pub fn is_victory(self) -> bool {
+        Self::has_four(self.mask ^ self.current_player)
+    }
+
     pub fn is_full(self) -> bool {
         self.filled == Self::SQUARES
     }
@@ -238,17 +408,25 @@ impl Board {
         self.current_player + self.mask
     }
     
-    pub fn symmetric_key(key: u64) -> u64 { Self::symmetric(key) }
+    pub fn symmetric_key(key: u64) -> u64 {
+        Self::symmetric(key)
+    }
+
+    //Same key for a board and its mirror image, as they have the same score
+    pub fn canonical_key(self) -> u64 {
+        let key = self.key();
+        key.min(Self::symmetric_key(key))
+    }
     
-    fn playable_positions(self) -> u64 {
+    pub(crate) fn playable_positions(self) -> u64 {
         (Self::BOTTOM + self.mask) & Self::BOARD_MASK
     }
 
-    fn my_winning_positions(self) -> u64 {
+    pub(crate) fn my_winning_positions(self) -> u64 {
         Self::winning_positions(self.current_player, self.mask)
     }
 
-    fn opponent_winning_positions(self) -> u64 {
+    pub(crate) fn opponent_winning_positions(self) -> u64 {
         Self::winning_positions(self.mask ^ self.current_player, self.mask)
     }
 
@@ -286,6 +464,17 @@ impl Board {
         count
     }
 
+    pub fn opponent_parity_threats(self) -> u32 {
+        //White wins the zugzwang on odd rows (counting from 1), Black on even rows
+        let good_rows = match self.player_to_play().opponent() {
+            Player::White => Self::ODD_ROWS,
+            Player::Black => Self::BOARD_MASK ^ Self::ODD_ROWS,
+        };
+        (self.opponent_winning_positions() & good_rows).count_ones()
+    }
+
+    /// # Safety
+    /// `move_mask` must be the mask of a single playable square of this board.
     pub unsafe fn make_move_unchecked(self, move_mask: u64) -> Board {
         Self {
             current_player: self.current_player ^ self.mask, //flip all values
@@ -294,3 +483,235 @@ impl Board {
         }
     }
 }
+
+//"{:#}" renders the discs with ANSI colours (White in yellow, Black in red)
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in (0..Self::HEIGHT).rev() {
+            for column in 0..Self::WIDTH {
+                let square = self.get_square(column, row).unwrap();
+                let colour = match square {
+                    Square::Taken(Player::White) if f.alternate() => "\x1b[33m",
+                    Square::Taken(Player::Black) if f.alternate() => "\x1b[31m",
+                    _ => "",
+                };
+                if colour.is_empty() {
+                    write!(f, " {}", square)?;
+                } else {
+                    write!(f, " {}{}\x1b[0m", colour, square)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        for column in 0..Self::WIDTH {
+            write!(f, " {}", column + 1)?;
+        }
+        writeln!(f)?;
+        if self.is_victory() {
+            write!(f, "{} won", self.player_to_play().opponent())
+        } else if self.is_full() {
+            write!(f, "Draw")
+        } else {
+            write!(f, "{} to play", self.player_to_play())
+        }
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const BITS: usize = (Board::WIDTH * (Board::HEIGHT + 1)) as usize;
+        f.debug_struct("Board")
+            .field("current_player", &format_args!("{:#0w$b}", self.current_player, w = BITS + 2))
+            .field("mask", &format_args!("{:#0w$b}", self.mask, w = BITS + 2))
+            .field("filled", &self.filled)
+            .finish()
+    }
+}
+
+//A board has no move history, so it is (de)serialized as its grid string (notation::format_grid)
+#[cfg(feature = "serialization")]
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&notation::format_grid(*self))
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        notation::parse_grid(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Grid = [[Square; Board::WIDTH as usize]; Board::HEIGHT as usize];
+
+    fn grid(rows_from_top: [&str; Board::HEIGHT as usize]) -> Grid {
+        let mut grid = [[Square::Empty; Board::WIDTH as usize]; Board::HEIGHT as usize];
+        for (index, row) in rows_from_top.iter().enumerate() {
+            for (column, character) in row.chars().enumerate() {
+                grid[Board::HEIGHT as usize - 1 - index][column] = match character {
+                    'x' => Square::Taken(Player::White),
+                    'o' => Square::Taken(Player::Black),
+                    _ => Square::Empty,
+                };
+            }
+        }
+        grid
+    }
+
+    fn to_grid(board: Board) -> Grid {
+        let mut grid = [[Square::Empty; Board::WIDTH as usize]; Board::HEIGHT as usize];
+        for row in 0..Board::HEIGHT {
+            for column in 0..Board::WIDTH {
+                grid[row as usize][column as usize] = board.get_square(column, row).unwrap();
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn from_grid_reachable_positions() {
+        for line in include_str!("./test_sets/Test_L2_R1").lines().take(100) {
+            let moves = line.split_whitespace().next().unwrap();
+            for length in 0..=moves.len() {
+                let board = Board::from_string(&moves[..length]).unwrap();
+                assert_eq!(Board::from_grid(&to_grid(board)), Ok(board));
+            }
+        }
+        let won = Board::from_string("1212121").unwrap();
+        assert_eq!(Board::from_grid(&to_grid(won)), Ok(won));
+    }
+
+    #[test]
+    fn from_grid_illegal_positions() {
+        let error = |rows| Board::from_grid(&grid(rows)).err();
+        assert_eq!(
+            error([".......", ".......", ".......", ".......", "x......", "......."]),
+            Some(GridError::FloatingDisc { column: 0, row: 1 })
+        );
+        assert_eq!(
+            error([".......", ".......", ".......", ".......", ".......", "xx....."]),
+            Some(GridError::WrongDiscCount { white: 2, black: 0 })
+        );
+        assert_eq!(
+            error([".......", ".......", "xo.....", "xo.....", "xo.....", "xo....."]),
+            Some(GridError::BothPlayersWon)
+        );
+        assert_eq!(
+            error([".......", ".......", "x......", "x......", "x......", "xoo.oo."]),
+            Some(GridError::WrongWinner(Player::White))
+        );
+        assert_eq!(
+            error([".......", ".......", "x.....x", "xo...ox", "xo...ox", "xo.o.ox"]),
+            Some(GridError::ImpossibleWin(Player::White))
+        );
+
+        let error = Error::from(GridError::WrongDiscCount { white: 2, black: 0 });
+        assert_eq!(
+            error.to_string(),
+            "illegal position: wrong disc count: 2 for White and 0 for Black"
+        );
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), "wrong disc count: 2 for White and 0 for Black");
+    }
+
+    #[test]
+    fn move_errors() {
+        let board = Board::from_string("444444").unwrap();
+        assert!(matches!(board.make_move(3), Err(Error::ColumnFull { column: 3 })));
+        assert!(matches!(board.make_move(7), Err(Error::ColumnOutOfRange { column: 7 })));
+        let won = Board::from_string("1212121").unwrap();
+        assert!(matches!(won.make_move(3), Err(Error::GameOver)));
+
+        //from_string keeps the cause of the illegal move
+        assert!(matches!(
+            Board::from_string("1111111"),
+            Err(Error::IllegalMove { index: 6, cause }) if matches!(*cause, Error::ColumnFull { column: 0 })
+        ));
+        assert!(matches!(
+            Board::from_string("12121212"),
+            Err(Error::IllegalMove { index: 7, cause }) if matches!(*cause, Error::GameOver)
+        ));
+    }
+
+    #[test]
+    fn symmetric() {
+        let board = Board::from_string("1234553").unwrap();
+        let mirror = Board::from_string("7654335").unwrap();
+        assert_eq!(board.symmetric_board(), mirror);
+        assert_eq!(Board::symmetric_key(board.key()), mirror.key());
+        assert_eq!(board.canonical_key(), mirror.canonical_key());
+
+        let full_column = Board::from_string("1111112").unwrap(); //The key uses the extra top bit
+        let mirror = Board::from_string("7777776").unwrap();
+        assert_eq!(Board::symmetric_key(full_column.key()), mirror.key());
+    }
+
+    //Column by column mirror, the reference for Board::symmetric
+    fn symmetric_reference(values: u64) -> u64 {
+        let column_bits: u64 = (1 << (Board::HEIGHT + 1)) - 1;
+        let mut result = 0;
+        for column in 0..Board::WIDTH {
+            let target = Board::WIDTH - 1 - column;
+            let selected = (values >> (column * (Board::HEIGHT + 1))) & column_bits;
+            result |= selected << (target * (Board::HEIGHT + 1));
+        }
+        result
+    }
+
+    fn check_symmetric(board: Board) {
+        for values in [board.current_player, board.mask, board.key()] {
+            assert_eq!(Board::symmetric(values), symmetric_reference(values), "{:?}", board);
+        }
+    }
+
+    fn check_symmetric_recursively(board: Board, depth: u32) {
+        check_symmetric(board);
+        if depth > 0 {
+            for column in 0..Board::WIDTH {
+                if let Ok(new_board) = board.make_move(column) {
+                    check_symmetric_recursively(new_board, depth - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_matches_reference() {
+        check_symmetric_recursively(Board::empty(), 5); //Every position up to 5 moves
+
+        let mut state: u64 = 0x9e3779b97f4a7c15; //xorshift64, fixed seed
+        for _ in 0..10000 {
+            let mut board = Board::empty();
+            while !board.game_over() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                if let Ok(new_board) = board.make_move((state % Board::WIDTH as u64) as u32) {
+                    board = new_board;
+                    check_symmetric(board);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        let board = Board::from_string("44536").unwrap();
+        assert_eq!(
+            board.to_string(),
+            " . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . o . . .\n . . o x x x .\n 1 2 3 4 5 6 7\nBlack to play"
+        );
+        assert!(Board::from_string("1212121").unwrap().to_string().ends_with("White won"));
+        assert!(format!("{:#}", board).contains("\x1b[33mx\x1b[0m"));
+        assert_eq!(
+            format!("{:?}", Board::from_string("1").unwrap()),
+            format!("Board {{ current_player: 0b{:049b}, mask: 0b{:049b}, filled: 1 }}", 0, 1)
+        );
+    }
+}
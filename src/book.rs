@@ -1,8 +1,11 @@
+use crate::error::Error;
+use crate::score::Score;
 use crate::{Board, Solution, Solver};
+use memmap2::Mmap;
+use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
-use std::io;
-use std::io::Write;
+use std::io::{BufReader, BufWriter, Write};
 use std::ops::Div;
 use std::path::Path;
 use std::time::Instant;
@@ -39,12 +42,20 @@ fn generate_opening_boards(move_count: u32) -> Vec<BoardUpToSymmetry> {
     result.into_iter().collect()
 }
 
-fn encode_key_value(key: u64, value: i32) -> u64 {
-    (key << 8) | ((value as u8) as u64)
+//Every position after move_count moves, one per pair of mirror images
+pub fn opening_boards(move_count: u32) -> Vec<Board> {
+    generate_opening_boards(move_count)
+        .into_iter()
+        .map(|board| board.0)
+        .collect()
 }
 
-fn decode_key_value(code: u64) -> (u64, i32) {
-    (code >> 8, (((code & 0xff) as u8) as i8) as i32)
+fn encode_key_value(key: u64, value: Score) -> u64 {
+    (key << 8) | ((value.value() as u8) as u64)
+}
+
+fn decode_key_value(code: u64) -> (u64, Score) {
+    (code >> 8, Score::new((((code & 0xff) as u8) as i8) as i32))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -64,36 +75,39 @@ impl Book {
         }
         let start = Instant::now();
         let mut solver = Solver::new();
-        Self {
-            table: boards
-                .iter()
-                .enumerate()
-                .flat_map(|(index, board)| {
-                    if log_progress && index > 0 && (index % 100 == 0) {
-                        let average_duration = (Instant::now() - start).div(index as u32);
-                        let remaining = average_duration * (count - index) as u32;
-                        println!("Solving position {} out of {}. Average duration {:?}, {:?} remaining.", index, count, average_duration, remaining);
-                    }
-                    match solver.solve(board.0) {
-                        Solution::Solved { score, .. } => {
-                            Some(encode_key_value(board.0.key(), score))
-                        }
-                        _ => None,
-                    }
-                })
-                .collect(),
+        let mut table = Vec::with_capacity(count);
+        for (chunk_index, chunk) in boards.chunks(100).enumerate() {
+            //Solved by chunks to log the progress
+            for solved in solver.solve_batch(chunk.iter().map(|board| board.0), 1) {
+                if let Solution::Solved { score, .. } = solved.solution {
+                    table.push(encode_key_value(solved.board.key(), score));
+                }
+            }
+            let index = 100 * chunk_index + chunk.len();
+            if log_progress && index < count {
+                let average_duration = (Instant::now() - start).div(index as u32);
+                let remaining = average_duration * (count - index) as u32;
+                println!("Solving position {} out of {}. Average duration {:?}, {:?} remaining.", index, count, average_duration, remaining);
+            }
         }
+        Self { table }
     }
     
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
         let mut file = File::create(path)?;
-        let data = bincode::serialize(self).unwrap();
-        file.write_all(&data)
+        let data = bincode::serialize(self)?;
+        file.write_all(&data)?;
+        Ok(())
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(bincode::deserialize_from(BufReader::new(file))?)
     }
 }
 
 pub struct LoadedBook {
-    table: HashMap<u64, i32>,
+    table: HashMap<u64, Score>,
 }
 
 impl LoadedBook {
@@ -109,4 +123,87 @@ impl LoadedBook {
                 .collect(),
         }
     }
+
+    pub fn get(&self, board: Board) -> Option<Score> {
+        self.table.get(&board.key()).copied()
+    }
+}
+
+//A book file mapped in memory, shared by the processes mapping it instead of being loaded. The
+//file holds the magic bytes, the version and the number of entries, then the entries sorted by
+//key, all little endian. The entries are encoded as in Book, for the boards normalized with
+//BoardUpToSymmetry.
+pub struct MappedBook {
+    map: Mmap,
+    len: usize,
+}
+
+impl MappedBook {
+    const MAGIC: &'static [u8; 4] = b"C4BK";
+    const VERSION: u32 = 1;
+    const HEADER_SIZE: usize = 16;
+
+    pub fn write<P: AsRef<Path>>(book: &Book, path: P) -> Result<(), Error> {
+        let mut table: Vec<u64> = book.table.clone();
+        table.sort_unstable();
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+        writer.write_all(&(table.len() as u64).to_le_bytes())?;
+        for code in table {
+            writer.write_all(&code.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    //The file must not be modified while it is mapped
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < Self::HEADER_SIZE || &map[..4] != Self::MAGIC {
+            return Err(Error::InvalidBookHeader);
+        }
+        let version = u32::from_le_bytes(map[4..8].try_into().unwrap());
+        if version != Self::VERSION {
+            return Err(Error::UnsupportedBookVersion { version });
+        }
+        let len = u64::from_le_bytes(map[8..16].try_into().unwrap());
+        if len.checked_mul(8) != Some((map.len() - Self::HEADER_SIZE) as u64) {
+            return Err(Error::WrongBookSize { len });
+        }
+        Ok(Self {
+            map,
+            len: len as usize,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn code(&self, index: usize) -> u64 {
+        let start = Self::HEADER_SIZE + 8 * index;
+        u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap())
+    }
+
+    //Binary search in the mapped entries
+    pub fn get(&self, board: Board) -> Option<Score> {
+        let key = BoardUpToSymmetry::new(board).0.key();
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (middle_key, score) = decode_key_value(self.code(middle));
+            match middle_key.cmp(&key) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Some(score),
+            }
+        }
+        None
+    }
 }
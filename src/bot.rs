@@ -0,0 +1,175 @@
+use crate::board::Board;
+use crate::heuristic::HeuristicEngine;
+use crate::random::Random;
+use crate::score::Score;
+use crate::{LazySolver, COLUMN_ORDER};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+    Perfect,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BotConfig {
+    pub depth: Option<u32>,       //HeuristicEngine depth, None to solve (slow in the opening)
+    pub temperature: f64,         //Softmax temperature over the move scores, 0 for the best move
+    pub mistake_probability: f64, //Chance to play a random column instead
+}
+
+impl Difficulty {
+    pub fn config(self) -> BotConfig {
+        let (depth, temperature, mistake_probability) = match self {
+            Difficulty::Beginner => (Some(1), 8.0, 0.3),
+            Difficulty::Easy => (Some(2), 4.0, 0.15),
+            Difficulty::Medium => (Some(4), 2.0, 0.05),
+            Difficulty::Hard => (Some(8), 0.5, 0.0),
+            Difficulty::Perfect => (None, 0.0, 0.0),
+        };
+        BotConfig {
+            depth,
+            temperature,
+            mistake_probability,
+        }
+    }
+}
+
+//Plays with controlled randomness, the same seed gives the same moves
+pub struct Bot {
+    config: BotConfig,
+    solver: LazySolver,
+    random: Random,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self::with_config(difficulty.config(), seed)
+    }
+
+    pub fn with_config(config: BotConfig, seed: u64) -> Self {
+        Self {
+            config,
+            solver: LazySolver::default(),
+            random: Random::new(seed),
+        }
+    }
+
+    pub fn config(&self) -> BotConfig {
+        self.config
+    }
+
+    fn move_scores(&mut self, board: Board) -> [Option<i32>; Board::WIDTH as usize] {
+        match self.config.depth {
+            Some(depth) => HeuristicEngine::new(depth).move_scores(board),
+            None => {
+                let scores = self.solver.get().analyze(board).scores;
+                scores.map(|score| score.map(Score::value))
+            }
+        }
+    }
+
+    //None if the game is over
+    pub fn choose_move(&mut self, board: Board) -> Option<u32> {
+        if board.game_over() {
+            return None;
+        }
+        let columns: Vec<u32> = COLUMN_ORDER
+            .into_iter()
+            .filter(|&column| board.can_play(column))
+            .collect();
+        if self.random.next_f64() < self.config.mistake_probability {
+            let index = self.random.next_u64() % columns.len() as u64;
+            return Some(columns[index as usize]);
+        }
+
+        let scores = self.move_scores(board);
+        let best = columns
+            .iter()
+            .filter_map(|&column| scores[column as usize])
+            .max()
+            .unwrap(); //A playable column has a score
+        if self.config.temperature <= 0.0 {
+            return columns
+                .into_iter()
+                .find(|&column| scores[column as usize] == Some(best));
+        }
+        let weights: Vec<f64> = columns
+            .iter()
+            .map(|&column| {
+                let score = scores[column as usize].unwrap();
+                ((score - best) as f64 / self.config.temperature).exp()
+            })
+            .collect();
+        let mut target = self.random.next_f64() * weights.iter().sum::<f64>();
+        for (&column, weight) in columns.iter().zip(weights) {
+            if target < weight {
+                return Some(column);
+            }
+            target -= weight;
+        }
+        columns.last().copied() //Rounding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_positions;
+    use crate::Solver;
+
+    fn play_game(white: &mut Bot, black: &mut Bot) -> Vec<u32> {
+        let mut board = Board::empty();
+        let mut moves = Vec::new();
+        while let Some(column) = if moves.len() % 2 == 0 {
+            white.choose_move(board)
+        } else {
+            black.choose_move(board)
+        } {
+            board = board.make_move(column).unwrap();
+            moves.push(column);
+        }
+        assert!(board.game_over());
+        moves
+    }
+
+    #[test]
+    fn deterministic() {
+        let game = |seed| {
+            play_game(
+                &mut Bot::new(Difficulty::Beginner, seed),
+                &mut Bot::new(Difficulty::Easy, seed + 1),
+            )
+        };
+        assert_eq!(game(1), game(1));
+        assert_ne!(game(1), game(3));
+    }
+
+    #[test]
+    fn perfect_play() {
+        let mut bot = Bot::new(Difficulty::Perfect, 0);
+        let mut solver = Solver::new();
+        for (board, _) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(20) {
+            assert_eq!(bot.choose_move(board), solver.analyze(board).best_move);
+        }
+        assert_eq!(bot.choose_move(Board::from_string("1212121").unwrap()), None);
+    }
+
+    #[test]
+    fn mistakes() {
+        //Black must block the vertical three: a bot that always errs sometimes misses it
+        let board = Board::from_string("12121").unwrap();
+        let config = BotConfig {
+            depth: Some(2),
+            temperature: 0.0,
+            mistake_probability: 1.0,
+        };
+        let mut bot = Bot::with_config(config, 42);
+        assert!((0..20).any(|_| bot.choose_move(board) != Some(0)));
+
+        let mut bot = Bot::new(Difficulty::Hard, 42);
+        assert!((0..20).all(|_| bot.choose_move(board) == Some(0)));
+    }
+}
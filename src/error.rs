@@ -0,0 +1,111 @@
+use crate::board::GridError;
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    //Moves
+    ColumnOutOfRange { column: u32 },
+    ColumnFull { column: u32 },
+    GameOver,
+
+    //Parsing, indices are counted in characters
+    InvalidCharacter { index: usize, ch: char },
+    IllegalMove { index: usize, cause: Box<Error> }, //ColumnFull or GameOver
+    WrongRowLength { index: usize }, //The row ending at this index is too short or too long
+    WrongRowCount,
+    FloatingDisc { index: usize }, //The disc at this index has an empty square below it
+    IllegalPosition(GridError),
+
+    //Game records
+    InvalidHeader { index: usize }, //The header starting at this index is not [Name "value"]
+    UnclosedComment { index: usize },
+    InvalidScore { index: usize }, //In the comment starting at this index
+    ResultMismatch, //The results in the headers, after the moves and on the board disagree
+    UnsupportedSize(String),
+    InvalidHeaderName(String), //Passed to GameRecord::set_header
+
+    //Opening book
+    BookIo(io::Error),
+    BookFormat(bincode::Error),
+    InvalidBookHeader,
+    UnsupportedBookVersion { version: u32 },
+    WrongBookSize { len: u64 }, //The file is not as long as the header says
+
+    //Transposition table dumps
+    TableIo(io::Error),
+    InvalidTableHeader,
+    UnsupportedTableVersion { version: u32 },
+    WrongTableSize { size: u64 }, //The dump has this number of entries, unlike the table
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ColumnOutOfRange { column } => write!(f, "column {} is out of range", column),
+            Error::ColumnFull { column } => write!(f, "column {} is full", column),
+            Error::GameOver => write!(f, "the game is over"),
+            Error::InvalidCharacter { index, ch } => {
+                write!(f, "invalid character {:?} at index {}", ch, index)
+            }
+            Error::IllegalMove { index, cause } => {
+                write!(f, "illegal move at index {}: {}", index, cause)
+            }
+            Error::WrongRowLength { index } => write!(f, "wrong row length at index {}", index),
+            Error::WrongRowCount => write!(f, "wrong number of rows"),
+            Error::FloatingDisc { index } => write!(f, "floating disc at index {}", index),
+            Error::IllegalPosition(error) => write!(f, "illegal position: {}", error),
+            Error::InvalidHeader { index } => write!(f, "invalid header at index {}", index),
+            Error::UnclosedComment { index } => write!(f, "unclosed comment at index {}", index),
+            Error::InvalidScore { index } => write!(f, "invalid score at index {}", index),
+            Error::ResultMismatch => write!(f, "the game results disagree"),
+            Error::UnsupportedSize(size) => write!(f, "unsupported board size {:?}", size),
+            Error::InvalidHeaderName(name) => write!(f, "invalid header name {:?}", name),
+            Error::BookIo(error) => write!(f, "book I/O error: {}", error),
+            Error::BookFormat(error) => write!(f, "invalid book format: {}", error),
+            Error::InvalidBookHeader => write!(f, "not a mapped book file"),
+            Error::UnsupportedBookVersion { version } => {
+                write!(f, "unsupported book version {}", version)
+            }
+            Error::WrongBookSize { len } => write!(f, "book file too short or too long for {} entries", len),
+            Error::TableIo(error) => write!(f, "transposition table I/O error: {}", error),
+            Error::InvalidTableHeader => write!(f, "not a transposition table dump"),
+            Error::UnsupportedTableVersion { version } => {
+                write!(f, "unsupported transposition table version {}", version)
+            }
+            Error::WrongTableSize { size } => {
+                write!(f, "transposition table dump of {} entries has the wrong size", size)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IllegalMove { cause, .. } => Some(cause.as_ref()),
+            Error::IllegalPosition(error) => Some(error),
+            Error::BookIo(error) => Some(error),
+            Error::BookFormat(error) => Some(error),
+            Error::TableIo(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<GridError> for Error {
+    fn from(error: GridError) -> Self {
+        Error::IllegalPosition(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::BookIo(error)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(error: bincode::Error) -> Self {
+        Error::BookFormat(error)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::board::Board;
+use crate::error::Error;
+use crate::notation;
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Game {
+    history: Vec<Board>, //history[i] is the board after i moves, the last one is the current board
+    moves: Vec<u32>,
+    undone: Vec<u32>, //Moves available for redo, the next one is last
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self {
+            history: vec![Board::empty()],
+            moves: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        let (_, moves) = notation::parse_digits(string)?;
+        let mut game = Self::new();
+        for column in moves {
+            game.play(column).unwrap(); //Already checked while parsing
+        }
+        Ok(game)
+    }
+
+    pub fn board(&self) -> Board {
+        *self.history.last().unwrap() //There is always at least the initial board
+    }
+
+    pub fn moves(&self) -> &[u32] {
+        &self.moves
+    }
+
+    pub fn play(&mut self, column: u32) -> Result<(), Error> {
+        let board = self.board().make_move(column)?;
+        self.history.push(board);
+        self.moves.push(column);
+        if self.undone.last() == Some(&column) {
+            self.undone.pop(); //Same move as the redo line: keep the rest of it
+        } else {
+            self.undone.clear();
+        }
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn undo(&mut self) -> Option<u32> {
+        let column = self.moves.pop()?;
+        self.history.pop();
+        self.undone.push(column);
+        Some(column)
+    }
+
+    pub fn redo(&mut self) -> Option<u32> {
+        let column = *self.undone.last()?;
+        self.play(column).ok()?; //Pops the redo line as it is the same move
+        Some(column)
+    }
+
+    pub fn to_move_string(&self) -> String {
+        notation::format_digits(&self.moves)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//A game is (de)serialized as its move string
+#[cfg(feature = "serialization")]
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_move_string())
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Game::from_string(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_string_round_trip() {
+        let string = "12345671234567";
+        let game = Game::from_string(string).unwrap();
+        assert_eq!(game.to_move_string(), string);
+        assert_eq!(game.moves(), &[0, 1, 2, 3, 4, 5, 6, 0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(game.board(), Board::from_string(string).unwrap());
+        assert!(Game::from_string("4444444").is_err());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut game = Game::from_string("4453").unwrap();
+        assert_eq!(game.undo(), Some(2));
+        assert_eq!(game.undo(), Some(4));
+        assert_eq!(game.board(), Board::from_string("44").unwrap());
+        assert_eq!(game.redo(), Some(4));
+        assert_eq!(game.to_move_string(), "445");
+        assert_eq!(game.board(), Board::from_string("445").unwrap());
+
+        game.play(2).unwrap(); //Same as the undone move: redo line is kept
+        assert!(!game.can_redo());
+        game.undo();
+        game.play(0).unwrap(); //Different move: redo line is dropped
+        assert!(!game.can_redo());
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.to_move_string(), "4451");
+
+        while game.can_undo() {
+            game.undo();
+        }
+        assert_eq!(game.board(), Board::empty());
+        assert_eq!(game.undo(), None);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::board::{Board, Player};
+use crate::{Engine, COLUMN_ORDER};
+
+//Forced wins score above any static evaluation, sooner wins higher
+const WIN: i32 = 1 << 16;
+
+//Depth-limited alpha-beta with a static evaluation at the leaves, for play without solving
+pub struct HeuristicEngine {
+    depth: u32, //In plies
+}
+
+impl HeuristicEngine {
+    pub fn new(depth: u32) -> Self {
+        Self { depth }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: u32) {
+        self.depth = depth;
+    }
+
+    //For the player to play: threats on the rows good for the zugzwang count double, and discs
+    //near the center count more as they belong to more fours
+    pub fn evaluate(board: Board) -> i32 {
+        let good_rows = |player: Player| match player {
+            Player::White => Board::ODD_ROWS,
+            Player::Black => !Board::ODD_ROWS,
+        };
+        let threats = |winning_positions: u64, player: Player| {
+            let good = (winning_positions & good_rows(player)).count_ones() as i32;
+            4 * good + 2 * (winning_positions.count_ones() as i32 - good)
+        };
+        let center = |squares: u64| -> i32 {
+            (0..Board::WIDTH)
+                .map(|column| {
+                    let weight = Board::WIDTH / 2 - column.abs_diff(Board::WIDTH / 2);
+                    (squares & Board::column_mask(column)).count_ones() as i32 * weight as i32
+                })
+                .sum()
+        };
+        let player = board.player_to_play();
+        threats(board.my_winning_positions(), player)
+            - threats(board.opponent_winning_positions(), player.opponent())
+            + center(board.player_squares())
+            - center(board.opponent_squares())
+    }
+
+    fn negamax(board: Board, depth: u32, mut alpha: i32, beta: i32) -> i32 {
+        if board.has_winning_move() {
+            return WIN + (Board::SQUARES as i32 + 1 - board.filled_squares() as i32) / 2;
+        }
+        let possible = board.non_losing_moves();
+        if possible == 0 {
+            return -WIN - (Board::SQUARES as i32 - board.filled_squares() as i32) / 2; //The opponent wins next move
+        }
+        if board.filled_squares() >= Board::SQUARES - 2 {
+            return 0; //Draw
+        }
+        if depth == 0 {
+            return Self::evaluate(board);
+        }
+        for column in COLUMN_ORDER {
+            let move_mask = possible & Board::column_mask(column);
+            if move_mask == 0 {
+                continue;
+            }
+            let new_board = unsafe { board.make_move_unchecked(move_mask) };
+            let score = -Self::negamax(new_board, depth - 1, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    //Score of the board for the player to play, None if the game is over
+    pub fn score(&self, board: Board) -> Option<i32> {
+        if board.game_over() {
+            return None;
+        }
+        Some(Self::negamax(board, self.depth, -2 * WIN, 2 * WIN))
+    }
+
+    //Score of each playable column for the player to play, searched to the same depth as score
+    pub fn move_scores(&self, board: Board) -> [Option<i32>; Board::WIDTH as usize] {
+        let mut scores = [None; Board::WIDTH as usize];
+        if board.game_over() {
+            return scores;
+        }
+        for column in 0..Board::WIDTH {
+            scores[column as usize] = match board.make_move(column) {
+                Ok(new_board) if new_board.is_victory() => {
+                    Some(WIN + (Board::SQUARES as i32 + 1 - board.filled_squares() as i32) / 2)
+                }
+                Ok(new_board) if new_board.is_full() => Some(0),
+                Ok(new_board) => Some(-Self::negamax(
+                    new_board,
+                    self.depth.saturating_sub(1),
+                    -2 * WIN,
+                    2 * WIN,
+                )),
+                Err(_) => None,
+            };
+        }
+        scores
+    }
+
+    //The first best column in COLUMN_ORDER, None if the game is over
+    pub fn best_move(&self, board: Board) -> Option<u32> {
+        let scores = self.move_scores(board);
+        let mut best_move: Option<u32> = None;
+        for column in COLUMN_ORDER {
+            let score = scores[column as usize];
+            if score.is_some() && best_move.is_none_or(|best| score > scores[best as usize]) {
+                best_move = Some(column);
+            }
+        }
+        best_move
+    }
+}
+
+impl Engine for HeuristicEngine {
+    fn best_move(&mut self, board: Board) -> Option<u32> {
+        HeuristicEngine::best_move(self, board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_positions;
+
+    #[test]
+    fn tactics() {
+        let engine = HeuristicEngine::new(4);
+        assert_eq!(engine.best_move(Board::empty()), Some(3));
+        //White wins on the bottom row
+        let board = Board::from_string("445566").unwrap();
+        assert_eq!(engine.best_move(board), Some(2));
+        assert!(engine.score(board).unwrap() > WIN);
+        //Black blocks the vertical three
+        assert_eq!(engine.best_move(Board::from_string("12121").unwrap()), Some(0));
+        //White's open three on the bottom row is too deep for a static evaluation
+        let board = Board::from_string("4455").unwrap();
+        assert!(HeuristicEngine::new(0).score(board).unwrap() < WIN);
+        assert!(engine.score(board).unwrap() > WIN);
+    }
+
+    #[test]
+    fn game_over() {
+        let engine = HeuristicEngine::new(2);
+        let board = Board::from_string("1212121").unwrap();
+        assert_eq!(engine.score(board), None);
+        assert_eq!(engine.best_move(board), None);
+        assert_eq!(engine.move_scores(board), [None; Board::WIDTH as usize]);
+    }
+
+    #[test]
+    fn move_scores_match_score() {
+        //The move made counts as the first ply of the search
+        let engine = HeuristicEngine::new(3);
+        for (board, _) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(100) {
+            let best = engine.move_scores(board).into_iter().flatten().max();
+            assert_eq!(best, engine.score(board), "{}", board);
+        }
+    }
+
+    #[test]
+    fn agrees_with_solver_on_forced_wins() {
+        //A win found by the engine is a real win
+        let engine = HeuristicEngine::new(6);
+        for (board, score) in test_positions(include_str!("./test_sets/Test_L3_R1")) {
+            match engine.score(board) {
+                Some(value) if value > WIN => assert_eq!(value - WIN, score.value(), "{}", board),
+                Some(value) if value < -WIN => assert_eq!(value + WIN, score.value(), "{}", board),
+                _ => {}
+            }
+        }
+    }
+}
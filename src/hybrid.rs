@@ -0,0 +1,119 @@
+use crate::board::Board;
+use crate::{Engine, LazySolver};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HybridConfig {
+    pub solve_from: u32,          //Filled squares from which the position is solved
+    pub max_estimated_nodes: f64, //Positions with a smaller estimated_nodes are solved too
+}
+
+impl Default for HybridConfig {
+    fn default() -> Self {
+        Self {
+            solve_from: 24,
+            max_estimated_nodes: 1e9, //Most middle game positions, solved in milliseconds
+        }
+    }
+}
+
+//Rough size of the tree left to solve: the square root of the number of move orders filling the
+//board, as alpha-beta visits about the square root of the tree in the best case
+pub fn estimated_nodes(board: Board) -> f64 {
+    let empty = board.empty_squares();
+    let ln_factorial = |n: u32| (2..=n).map(|k| (k as f64).ln()).sum::<f64>();
+    let columns: f64 = (0..Board::WIDTH)
+        .map(|column| ln_factorial((empty & Board::column_mask(column)).count_ones()))
+        .sum();
+    (0.5 * (ln_factorial(empty.count_ones()) - columns)).exp()
+}
+
+//Plays with another engine, then perfectly once the end of the game is small enough to solve
+pub struct HybridEngine<E> {
+    engine: E,
+    solver: LazySolver,
+    config: HybridConfig,
+}
+
+impl<E: Engine> HybridEngine<E> {
+    pub fn new(engine: E, config: HybridConfig) -> Self {
+        Self {
+            engine,
+            solver: LazySolver::default(),
+            config,
+        }
+    }
+
+    pub fn engine(&self) -> &E {
+        &self.engine
+    }
+
+    pub fn engine_mut(&mut self) -> &mut E {
+        &mut self.engine
+    }
+
+    pub fn config(&self) -> HybridConfig {
+        self.config
+    }
+
+    pub fn solves(&self, board: Board) -> bool {
+        board.filled_squares() >= self.config.solve_from
+            || estimated_nodes(board) <= self.config.max_estimated_nodes
+    }
+}
+
+impl<E: Engine> Engine for HybridEngine<E> {
+    fn best_move(&mut self, board: Board) -> Option<u32> {
+        if self.solves(board) {
+            self.solver.get().best_move(board)
+        } else {
+            self.engine.best_move(board)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::HeuristicEngine;
+    use crate::tests::test_positions;
+    use crate::Solver;
+
+    //Always plays the leftmost column
+    struct Leftmost;
+
+    impl Engine for Leftmost {
+        fn best_move(&mut self, board: Board) -> Option<u32> {
+            (0..Board::WIDTH).find(|&column| board.can_play(column) && !board.game_over())
+        }
+    }
+
+    #[test]
+    fn estimate() {
+        assert!((estimated_nodes(Board::empty()) / 3.74e15 - 1.0).abs() < 0.01);
+        let board = Board::from_string("4455").unwrap();
+        assert!(estimated_nodes(board) < estimated_nodes(Board::empty()));
+    }
+
+    #[test]
+    fn switches_to_solver() {
+        let config = HybridConfig {
+            solve_from: 30,
+            max_estimated_nodes: 0.0,
+        };
+        let mut hybrid = HybridEngine::new(Leftmost, config);
+        let mut solver = Solver::new();
+        for (board, _) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(50) {
+            let expected = if board.filled_squares() >= 30 {
+                solver.analyze(board).best_move
+            } else {
+                Leftmost.best_move(board)
+            };
+            assert_eq!(hybrid.solves(board), board.filled_squares() >= 30);
+            assert_eq!(hybrid.best_move(board), expected, "{}", board);
+        }
+
+        let hybrid = HybridEngine::new(HeuristicEngine::new(4), HybridConfig::default());
+        assert!(!hybrid.solves(Board::empty()));
+        assert_eq!(hybrid.engine().depth(), 4);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::board::Board;
+use crate::random::Random;
+use crate::score::Outcome;
+use crate::{Engine, LazySolver, COLUMN_ORDER};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    pub exploration: f64,         //UCT exploration constant
+    pub solve_below: u32,         //Positions with fewer empty squares are solved, 0 to never solve
+    pub heuristic_playouts: bool, //Playouts prefer the moves creating threats instead of random ones
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 10000,
+            exploration: std::f64::consts::SQRT_2,
+            solve_below: 12,
+            heuristic_playouts: true,
+        }
+    }
+}
+
+//Game-theoretic value for the player to play at a node (MCTS-Solver)
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Proof {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Proof {
+    fn reward(self) -> f64 {
+        match self {
+            Proof::Win => 1.0,
+            Proof::Draw => 0.5,
+            Proof::Loss => 0.0,
+        }
+    }
+}
+
+struct Node {
+    board: Board,
+    column: u32, //The move leading to this node
+    parent: Option<usize>,
+    children: Vec<usize>, //Empty until the node is expanded
+    visits: u32,
+    reward: f64, //Sum of the rewards of the player who moved into this node
+    proof: Option<Proof>,
+}
+
+//Monte Carlo tree search with UCT, where proven wins and losses are propagated up the tree
+pub struct Mcts {
+    config: MctsConfig,
+    solver: LazySolver,
+    random: Random,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig, seed: u64) -> Self {
+        Self {
+            config,
+            solver: LazySolver::default(),
+            random: Random::new(seed),
+        }
+    }
+
+    pub fn config(&self) -> MctsConfig {
+        self.config
+    }
+
+    fn prove(&mut self, board: Board) -> Option<Proof> {
+        if board.is_victory() {
+            return Some(Proof::Loss); //The previous player won
+        }
+        if board.is_full() {
+            return Some(Proof::Draw);
+        }
+        if board.has_winning_move() {
+            return Some(Proof::Win);
+        }
+        if board.non_losing_moves() == 0 {
+            return Some(Proof::Loss);
+        }
+        if Board::SQUARES - board.filled_squares() < self.config.solve_below {
+            let solution = self.solver.get().solve(board);
+            return Some(match solution.score().outcome() {
+                Outcome::Win => Proof::Win,
+                Outcome::Draw => Proof::Draw,
+                Outcome::Loss => Proof::Loss,
+            });
+        }
+        None
+    }
+
+    fn expand(&mut self, tree: &mut Vec<Node>, index: usize) {
+        let board = tree[index].board;
+        for column in COLUMN_ORDER {
+            if let Ok(new_board) = board.make_move(column) {
+                let proof = self.prove(new_board);
+                tree.push(Node {
+                    board: new_board,
+                    column,
+                    parent: Some(index),
+                    children: Vec::new(),
+                    visits: 0,
+                    reward: 0.0,
+                    proof,
+                });
+                let child = tree.len() - 1;
+                tree[index].children.push(child);
+            }
+        }
+    }
+
+    //A child lost for its player wins the node, and the node is lost if all its children are won
+    fn proof_from_children(tree: &[Node], index: usize) -> Option<Proof> {
+        let children = &tree[index].children;
+        let proofs = || children.iter().map(|&child| tree[child].proof);
+        if proofs().any(|proof| proof == Some(Proof::Loss)) {
+            Some(Proof::Win)
+        } else if children.is_empty() || proofs().any(|proof| proof.is_none()) {
+            None
+        } else if proofs().any(|proof| proof == Some(Proof::Draw)) {
+            Some(Proof::Draw)
+        } else {
+            Some(Proof::Loss)
+        }
+    }
+
+    fn select(&self, tree: &[Node], index: usize) -> usize {
+        let log_visits = (tree[index].visits as f64).ln();
+        let mut best = None;
+        let mut best_value = f64::NEG_INFINITY;
+        for &child in &tree[index].children {
+            let node = &tree[child];
+            let value = match node.proof {
+                Some(Proof::Win) => continue, //Won by the opponent
+                _ if node.visits == 0 => f64::INFINITY,
+                Some(proof) => 1.0 - proof.reward(),
+                None => node.reward / node.visits as f64,
+            } + self.config.exploration * (log_visits / node.visits as f64).sqrt();
+            if best.is_none() || value > best_value {
+                best = Some(child);
+                best_value = value;
+            }
+        }
+        best.unwrap() //The node would be lost otherwise
+    }
+
+    //Reward of the player to play, the game must not be over
+    fn playout(&mut self, mut board: Board) -> f64 {
+        let mut same_player = true;
+        let reward = loop {
+            if board.is_full() {
+                break 0.5;
+            }
+            if board.has_winning_move() {
+                break 1.0;
+            }
+            let mut moves = board.non_losing_moves();
+            if moves == 0 {
+                break 0.0;
+            }
+            if self.config.heuristic_playouts {
+                let mut best_moves = 0;
+                let mut best_threats = 0;
+                let mut remaining = moves;
+                while remaining != 0 {
+                    let move_mask = remaining & remaining.wrapping_neg();
+                    remaining ^= move_mask;
+                    let new_board = unsafe { board.make_move_unchecked(move_mask) };
+                    let threats = new_board.opponent_heuristic_score();
+                    if best_moves == 0 || threats > best_threats {
+                        best_moves = move_mask;
+                        best_threats = threats;
+                    } else if threats == best_threats {
+                        best_moves |= move_mask;
+                    }
+                }
+                moves = best_moves;
+            }
+            let mut index = self.random.next_u64() % moves.count_ones() as u64;
+            while index > 0 {
+                moves &= moves - 1;
+                index -= 1;
+            }
+            board = unsafe { board.make_move_unchecked(moves & moves.wrapping_neg()) };
+            same_player = !same_player;
+        };
+        if same_player {
+            reward
+        } else {
+            1.0 - reward
+        }
+    }
+
+    fn iterate(&mut self, tree: &mut Vec<Node>) {
+        let mut index = 0;
+        while tree[index].proof.is_none() && !tree[index].children.is_empty() {
+            index = self.select(tree, index);
+        }
+
+        let mut leaf = index;
+        if tree[index].proof.is_none() {
+            self.expand(tree, index);
+            match Self::proof_from_children(tree, index) {
+                Some(proof) => tree[index].proof = Some(proof),
+                None => {
+                    let unproven: Vec<usize> = tree[index]
+                        .children
+                        .iter()
+                        .copied()
+                        .filter(|&child| tree[child].proof.is_none())
+                        .collect();
+                    leaf = unproven[(self.random.next_u64() % unproven.len() as u64) as usize];
+                }
+            }
+        }
+
+        let reward = match tree[leaf].proof {
+            Some(proof) => proof.reward(),
+            None => self.playout(tree[leaf].board),
+        };
+        let mut reward = 1.0 - reward; //For the player who moved into the leaf
+        let mut node = Some(leaf);
+        while let Some(current) = node {
+            tree[current].visits += 1;
+            tree[current].reward += reward;
+            reward = 1.0 - reward;
+            node = tree[current].parent;
+        }
+
+        let mut node = tree[leaf].parent;
+        while let Some(current) = node {
+            if tree[current].proof.is_none() {
+                match Self::proof_from_children(tree, current) {
+                    Some(proof) => tree[current].proof = Some(proof),
+                    None => break,
+                }
+            }
+            node = tree[current].parent;
+        }
+    }
+
+    //A proven win if there is one, else the most visited move not proven to lose
+    pub fn best_move(&mut self, board: Board) -> Option<u32> {
+        if board.game_over() {
+            return None;
+        }
+        let mut tree = vec![Node {
+            board,
+            column: 0,
+            parent: None,
+            children: Vec::new(),
+            visits: 0,
+            reward: 0.0,
+            proof: None, //Not proven with the other nodes, as a move is needed anyway
+        }];
+        for _ in 0..self.config.iterations {
+            self.iterate(&mut tree);
+            if tree[0].proof.is_some() {
+                break;
+            }
+        }
+
+        let children = &tree[0].children;
+        let winning = |child: &&usize| tree[**child].proof == Some(Proof::Loss);
+        if let Some(&child) = children
+            .iter()
+            .filter(winning)
+            .find(|&&child| tree[child].board.is_victory())
+            .or_else(|| children.iter().find(winning))
+        {
+            return Some(tree[child].column); //Winning at once if possible
+        }
+        let mut best: Option<usize> = None;
+        let key = |child: usize| (tree[child].proof != Some(Proof::Win), tree[child].visits);
+        for &child in children {
+            if best.is_none_or(|best| key(child) > key(best)) {
+                best = Some(child);
+            }
+        }
+        best.map(|child| tree[child].column)
+    }
+}
+
+impl Engine for Mcts {
+    fn best_move(&mut self, board: Board) -> Option<u32> {
+        Mcts::best_move(self, board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_positions;
+    use crate::Solver;
+
+    fn config(iterations: u32, solve_below: u32) -> MctsConfig {
+        MctsConfig {
+            iterations,
+            solve_below,
+            ..MctsConfig::default()
+        }
+    }
+
+    #[test]
+    fn tactics() {
+        let mut mcts = Mcts::new(config(1000, 0), 0);
+        assert_eq!(mcts.best_move(Board::from_string("445566").unwrap()), Some(2));
+        assert_eq!(mcts.best_move(Board::from_string("12121").unwrap()), Some(0));
+        assert_eq!(mcts.best_move(Board::from_string("1212121").unwrap()), None);
+        assert_eq!(mcts.best_move(Board::empty()), Some(3));
+    }
+
+    #[test]
+    fn deterministic() {
+        let board = Board::from_string("4453").unwrap();
+        let mut random_playouts = config(300, 0);
+        random_playouts.heuristic_playouts = false;
+        let moves = |seed| {
+            let mut mcts = Mcts::new(random_playouts, seed);
+            (0..5).map(|_| mcts.best_move(board)).collect::<Vec<_>>()
+        };
+        assert_eq!(moves(7), moves(7));
+    }
+
+    #[test]
+    fn solver_fallback() {
+        //With the end of the game solved, the move keeps the outcome of the position
+        let mut mcts = Mcts::new(config(200, 20), 0);
+        let mut solver = Solver::new();
+        for (board, score) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(20) {
+            let column = mcts.best_move(board).unwrap();
+            let move_score = solver.analyze(board).scores[column as usize].unwrap();
+            assert_eq!(move_score.outcome(), score.outcome(), "{}", board);
+        }
+    }
+}
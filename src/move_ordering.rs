@@ -0,0 +1,75 @@
+use crate::board::Board;
+use crate::SearchConfig;
+
+//Computes the MoveSorter scores, a higher score is searched first
+pub struct MoveOrdering {
+    killer_moves: bool,
+    history_heuristic: bool,
+    threat_parity: bool,
+    killers: [[Option<u32>; 2]; Board::SQUARES as usize], //Last cutoff columns, by move count
+    history: [[u32; 64]; 2], //Cutoff statistics by player and square bit
+}
+
+impl MoveOrdering {
+    pub fn new(config: &SearchConfig) -> Self {
+        Self {
+            killer_moves: config.killer_moves,
+            history_heuristic: config.history_heuristic,
+            threat_parity: config.threat_parity,
+            killers: [[None; 2]; Board::SQUARES as usize],
+            history: [[0; 64]; 2],
+        }
+    }
+
+    //Keeps the statistics of previous searches, with less weight
+    pub fn age(&mut self) {
+        self.killers = [[None; 2]; Board::SQUARES as usize];
+        for player_history in self.history.iter_mut() {
+            for value in player_history.iter_mut() {
+                *value /= 2;
+            }
+        }
+    }
+
+    pub fn score(&self, board: Board, new_board: Board, move_mask: u64, hint: Option<u32>) -> u32 {
+        let column = Board::mask_column(move_mask);
+        if hint == Some(column) {
+            return u32::MAX;
+        }
+        //By priority: threats created, threats on good rows, center columns, killer slot, history
+        let mut score = new_board.opponent_heuristic_score() << 26;
+        if self.threat_parity {
+            score |= new_board.opponent_parity_threats() << 21;
+        }
+        score |= (Board::WIDTH / 2 - column.abs_diff(Board::WIDTH / 2)) << 19;
+        if self.killer_moves {
+            let killers = &self.killers[board.filled_squares() as usize];
+            if let Some(slot) = killers.iter().position(|&killer| killer == Some(column)) {
+                score |= (2 - slot as u32) << 17;
+            }
+        }
+        if self.history_heuristic {
+            let player = board.player_to_play() as usize;
+            let history = self.history[player][move_mask.trailing_zeros() as usize];
+            score |= history.min((1 << 17) - 1);
+        }
+        score
+    }
+
+    pub fn record_cutoff(&mut self, board: Board, move_mask: u64) {
+        if self.killer_moves {
+            let column = Board::mask_column(move_mask);
+            let killers = &mut self.killers[board.filled_squares() as usize];
+            if killers[0] != Some(column) {
+                killers[1] = killers[0];
+                killers[0] = Some(column);
+            }
+        }
+        if self.history_heuristic {
+            let depth = Board::SQUARES - board.filled_squares();
+            let player = board.player_to_play() as usize;
+            let value = &mut self.history[player][move_mask.trailing_zeros() as usize];
+            *value = value.saturating_add(depth * depth);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::board::{Board, GridError, Player, Square};
+use crate::error::Error;
+
+pub fn column_to_digit(column: u32) -> char {
+    char::from_digit(column + 1, 10).unwrap()
+}
+
+pub fn column_from_digit(character: char) -> Option<u32> {
+    match character.to_digit(10) {
+        Some(digit) if (1..=Board::WIDTH).contains(&digit) => Some(digit - 1),
+        _ => None,
+    }
+}
+
+pub fn column_to_letter(column: u32) -> char {
+    (b'a' + column as u8) as char
+}
+
+pub fn column_from_letter(character: char) -> Option<u32> {
+    let column = (character.to_ascii_lowercase() as u32).wrapping_sub('a' as u32);
+    if column < Board::WIDTH {
+        Some(column)
+    } else {
+        None
+    }
+}
+
+fn parse_moves(
+    string: &str,
+    column_from_char: fn(char) -> Option<u32>,
+) -> Result<(Board, Vec<u32>), Error> {
+    let mut board = Board::empty();
+    let mut moves = Vec::new();
+    for (index, character) in string.chars().enumerate() {
+        let column = column_from_char(character).ok_or(Error::InvalidCharacter {
+            index,
+            ch: character,
+        })?;
+        board = board
+            .make_move(column)
+            .map_err(|cause| Error::IllegalMove {
+                index,
+                cause: Box::new(cause),
+            })?;
+        moves.push(column);
+    }
+    Ok((board, moves))
+}
+
+//"4453": 1-based column digits, as used by Board::from_string
+pub fn parse_digits(string: &str) -> Result<(Board, Vec<u32>), Error> {
+    parse_moves(string, column_from_digit)
+}
+
+pub fn format_digits(moves: &[u32]) -> String {
+    moves
+        .iter()
+        .map(|&column| column_to_digit(column))
+        .collect()
+}
+
+//"ddec": a-g column letters, upper case is accepted when parsing
+pub fn parse_letters(string: &str) -> Result<(Board, Vec<u32>), Error> {
+    parse_moves(string, column_from_letter)
+}
+
+pub fn format_letters(moves: &[u32]) -> String {
+    moves
+        .iter()
+        .map(|&column| column_to_letter(column))
+        .collect()
+}
+
+//Rows from top to bottom separated by '/', 'x' for White, 'o' for Black and '.' for empty squares:
+//"......./......./......./......./...o.../..oxxx."
+pub fn format_grid(board: Board) -> String {
+    let mut result = String::new();
+    for row in (0..Board::HEIGHT).rev() {
+        for column in 0..Board::WIDTH {
+            result += &board.get_square(column, row).unwrap().to_string();
+        }
+        if row > 0 {
+            result.push('/');
+        }
+    }
+    result
+}
+
+pub fn parse_grid(string: &str) -> Result<Board, Error> {
+    let mut grid = [[Square::Empty; Board::WIDTH as usize]; Board::HEIGHT as usize];
+    let mut row = Board::HEIGHT as usize; //Rows are read from the top
+    let mut column = 0;
+    let mut positions = [[0; Board::WIDTH as usize]; Board::HEIGHT as usize];
+    for (index, character) in string.chars().chain(std::iter::once('/')).enumerate() {
+        if character == '/' {
+            if column != Board::WIDTH as usize {
+                return Err(Error::WrongRowLength { index });
+            }
+            if row == 0 {
+                return Err(Error::WrongRowCount);
+            }
+            row -= 1;
+            column = 0;
+            continue;
+        }
+        let square = match character {
+            '.' => Square::Empty,
+            'x' | 'X' => Square::Taken(Player::White),
+            'o' | 'O' => Square::Taken(Player::Black),
+            _ => {
+                return Err(Error::InvalidCharacter {
+                    index,
+                    ch: character,
+                })
+            }
+        };
+        if row == 0 {
+            return Err(Error::WrongRowCount);
+        }
+        if column == Board::WIDTH as usize {
+            return Err(Error::WrongRowLength { index });
+        }
+        grid[row - 1][column] = square;
+        positions[row - 1][column] = index;
+        column += 1;
+    }
+    if row != 0 {
+        return Err(Error::WrongRowCount);
+    }
+    Board::from_grid(&grid).map_err(|error| match error {
+        GridError::FloatingDisc { column, row } => Error::FloatingDisc {
+            index: positions[row as usize][column as usize],
+        },
+        error => Error::IllegalPosition(error),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_notations() {
+        let (board, moves) = parse_digits("4453").unwrap();
+        assert_eq!(board, Board::from_string("4453").unwrap());
+        assert_eq!(format_digits(&moves), "4453");
+        assert_eq!(format_letters(&moves), "ddec");
+        let (_, moves) = parse_letters("DdeC").unwrap();
+        assert_eq!(format_digits(&moves), "4453");
+
+        assert!(matches!(
+            parse_digits("4483"),
+            Err(Error::InvalidCharacter { index: 2, ch: '8' })
+        ));
+        assert!(matches!(
+            parse_letters("abh"),
+            Err(Error::InvalidCharacter { index: 2, ch: 'h' })
+        ));
+        assert!(matches!(
+            parse_digits("1111111"),
+            Err(Error::IllegalMove { index: 6, cause }) if matches!(*cause, Error::ColumnFull { column: 0 })
+        ));
+        assert!(matches!(
+            parse_letters("ababababc"),
+            Err(Error::IllegalMove { index: 7, cause }) if matches!(*cause, Error::GameOver)
+        ));
+    }
+
+    #[test]
+    fn grid_notation() {
+        let board = Board::from_string("44536").unwrap();
+        let grid = format_grid(board);
+        assert_eq!(grid, "......./......./......./......./...o.../..oxxx.");
+        assert_eq!(parse_grid(&grid).unwrap(), board);
+        assert_eq!(
+            parse_grid(&format_grid(Board::empty())).unwrap(),
+            Board::empty()
+        );
+
+        assert!(matches!(
+            parse_grid("......./......./......./......./...o.../..oxxy."),
+            Err(Error::InvalidCharacter { index: 45, ch: 'y' })
+        ));
+        assert!(matches!(
+            parse_grid("......./......./......./......./...o.../..oxxx"),
+            Err(Error::WrongRowLength { index: 46 })
+        ));
+        assert!(matches!(
+            parse_grid("......./......./......./......./...o..."),
+            Err(Error::WrongRowCount)
+        ));
+        assert!(matches!(
+            parse_grid("......./......./......./......./......./......./......./......."),
+            Err(Error::WrongRowCount)
+        ));
+        assert!(matches!(
+            parse_grid("......./......./......./......./...o..o/..oxxx."),
+            Err(Error::FloatingDisc { index: 38 })
+        ));
+        assert!(matches!(
+            parse_grid("......./......./......./......./......./..ox.o."),
+            Err(Error::IllegalPosition(GridError::WrongDiscCount {
+                white: 1,
+                black: 2
+            }))
+        ));
+    }
+}
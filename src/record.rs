@@ -0,0 +1,482 @@
+use crate::board::{Board, Player};
+use crate::error::Error;
+use crate::game::Game;
+use crate::notation;
+use crate::score::Score;
+use std::fmt;
+
+//A PGN-like text format:
+//
+//  [White "Alice"]
+//  [Black "Bob"]
+//  [Date "2024.05.01"]
+//  [Variant "Standard"]
+//  [Size "7x6"]
+//  [Result "1-0"]
+//
+//  1. 4 4 2. 4 {[%score 2] Threatening} 4 3. 4 5 4. 4 1-0
+//
+//Moves use the 1-based column digits of Board::from_string, and several of them may be written
+//together ("4453"). Move numbers are optional when parsing. A comment after a move may start with
+//a score, the solver score of the move for the player who played it, and a space. '\' escapes the
+//next character in header values and comments, the writer escapes '"' in values, and '}' and a
+//leading '[' in comments.
+
+const SIZE: &str = "7x6";
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unfinished,
+}
+
+impl GameResult {
+    pub fn of_board(board: Board) -> Self {
+        if board.is_victory() {
+            match board.player_to_play().opponent() {
+                Player::White => GameResult::WhiteWins,
+                Player::Black => GameResult::BlackWins,
+            }
+        } else if board.is_full() {
+            GameResult::Draw
+        } else {
+            GameResult::Unfinished
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unfinished => "*",
+        }
+    }
+
+    fn parse(string: &str) -> Option<Self> {
+        [
+            GameResult::WhiteWins,
+            GameResult::BlackWins,
+            GameResult::Draw,
+            GameResult::Unfinished,
+        ]
+        .into_iter()
+        .find(|result| result.as_str() == string)
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RecordMove {
+    pub column: u32,
+    pub score: Option<Score>, //For the player who played the move
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GameRecord {
+    pub headers: Vec<(String, String)>, //In order, without the result
+    pub moves: Vec<RecordMove>,
+    pub result: GameResult,
+}
+
+impl GameRecord {
+    //Unknown players and date are written as in PGN
+    pub fn from_game(game: &Game) -> Self {
+        let headers = [
+            ("White", "?"),
+            ("Black", "?"),
+            ("Date", "????.??.??"),
+            ("Variant", "Standard"),
+            ("Size", SIZE),
+        ];
+        Self {
+            headers: headers
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            moves: game
+                .moves()
+                .iter()
+                .map(|&column| RecordMove {
+                    column,
+                    score: None,
+                    comment: None,
+                })
+                .collect(),
+            result: GameResult::of_board(game.board()),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    //The name must be made of ASCII letters, digits and '_'. The result is set with the result field
+    pub fn set_header(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let valid_name = name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if name.is_empty() || !valid_name || name == "Result" {
+            return Err(Error::InvalidHeaderName(name.to_string()));
+        }
+        if name == "Size" && value != SIZE {
+            return Err(Error::UnsupportedSize(value.to_string()));
+        }
+        match self.headers.iter_mut().find(|(header, _)| header == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn columns(&self) -> Vec<u32> {
+        self.moves.iter().map(|record_move| record_move.column).collect()
+    }
+
+    pub fn to_game(&self) -> Result<Game, Error> {
+        let mut game = Game::new();
+        for &column in &self.columns() {
+            game.play(column)?;
+        }
+        Ok(game)
+    }
+
+    pub fn parse(string: &str) -> Result<Self, Error> {
+        let chars: Vec<char> = string.chars().collect();
+        let mut headers = Vec::new();
+        let mut header_result = None;
+        let mut moves: Vec<RecordMove> = Vec::new();
+        let mut board = Board::empty();
+        let mut result = None; //After the moves
+        let mut index = 0;
+        while index < chars.len() {
+            let ch = chars[index];
+            if ch.is_whitespace() {
+                index += 1;
+                continue;
+            }
+            if result.is_some() {
+                return Err(Error::InvalidCharacter { index, ch }); //Nothing after the result
+            }
+            match ch {
+                '[' if moves.is_empty() => {
+                    let (name, value, end) = parse_header(&chars, index)?;
+                    if name == "Result" {
+                        let parsed = GameResult::parse(&value);
+                        header_result = Some(parsed.ok_or(Error::InvalidHeader { index })?);
+                    } else {
+                        if name == "Size" && value != SIZE {
+                            return Err(Error::UnsupportedSize(value));
+                        }
+                        headers.push((name, value));
+                    }
+                    index = end;
+                }
+                '{' => {
+                    let (_, end) = parse_escaped(&chars, index + 1, '}')
+                        .ok_or(Error::UnclosedComment { index })?;
+                    let (score, start) =
+                        parse_score(&chars[..end], index + 1).ok_or(Error::InvalidScore { index })?;
+                    let (comment, _) = parse_escaped(&chars, start, '}')
+                        .ok_or(Error::UnclosedComment { index })?;
+                    let record_move = moves
+                        .last_mut()
+                        .ok_or(Error::InvalidCharacter { index, ch })?;
+                    record_move.score = score;
+                    record_move.comment = (!comment.is_empty()).then_some(comment);
+                    index = end + 1;
+                }
+                _ => {
+                    let end = (index..chars.len())
+                        .find(|&end| chars[end].is_whitespace() || chars[end] == '{')
+                        .unwrap_or(chars.len());
+                    let word: String = chars[index..end].iter().collect();
+                    let move_number = word.len() > 1
+                        && word.ends_with('.')
+                        && word[..word.len() - 1].chars().all(|ch| ch.is_ascii_digit());
+                    if let Some(parsed) = GameResult::parse(&word) {
+                        result = Some(parsed);
+                    } else if !move_number {
+                        for (offset, ch) in word.chars().enumerate() {
+                            let index = index + offset;
+                            let column = notation::column_from_digit(ch)
+                                .ok_or(Error::InvalidCharacter { index, ch })?;
+                            board = board
+                                .make_move(column)
+                                .map_err(|cause| Error::IllegalMove {
+                                    index,
+                                    cause: Box::new(cause),
+                                })?;
+                            moves.push(RecordMove {
+                                column,
+                                score: None,
+                                comment: None,
+                            });
+                        }
+                    }
+                    index = end;
+                }
+            }
+        }
+
+        let result = match (header_result, result) {
+            (Some(header), Some(result)) if header != result => return Err(Error::ResultMismatch),
+            (header, result) => result.or(header).unwrap_or(GameResult::of_board(board)),
+        };
+        if board.game_over() && result != GameResult::of_board(board) {
+            return Err(Error::ResultMismatch); //Games may end early, by resignation for instance
+        }
+        Ok(Self {
+            headers,
+            moves,
+            result,
+        })
+    }
+}
+
+//Returns the name, the value and the index after the header
+fn parse_header(chars: &[char], start: usize) -> Result<(String, String, usize), Error> {
+    let error = Error::InvalidHeader { index: start };
+    let mut index = start + 1;
+    let mut name = String::new();
+    while index < chars.len() && (chars[index].is_ascii_alphanumeric() || chars[index] == '_') {
+        name.push(chars[index]);
+        index += 1;
+    }
+    while index < chars.len() && chars[index] == ' ' {
+        index += 1;
+    }
+    if name.is_empty() || chars.get(index) != Some(&'"') {
+        return Err(error);
+    }
+    let (value, index) = match parse_escaped(chars, index + 1, '"') {
+        Some(parsed) => parsed,
+        None => return Err(error),
+    };
+    if chars.get(index + 1) != Some(&']') {
+        return Err(error);
+    }
+    Ok((name, value, index + 2))
+}
+
+//Returns the unescaped text from start to the delimiter and the index of the delimiter
+fn parse_escaped(chars: &[char], start: usize, delimiter: char) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut index = start;
+    loop {
+        match *chars.get(index)? {
+            ch if ch == delimiter => return Some((text, index)),
+            '\\' => {
+                text.push(*chars.get(index + 1)?);
+                index += 2;
+            }
+            ch => {
+                text.push(ch);
+                index += 1;
+            }
+        }
+    }
+}
+
+//The score written at the start of a comment, and the index of the comment text after it
+fn parse_score(chars: &[char], start: usize) -> Option<(Option<Score>, usize)> {
+    const PREFIX: &str = "[%score";
+    if !chars[start..].iter().copied().take(PREFIX.len()).eq(PREFIX.chars()) {
+        return Some((None, start));
+    }
+    let end = (start..chars.len()).find(|&end| chars[end] == ']')?;
+    let value: String = chars[start + PREFIX.len()..end].iter().collect();
+    let value: i32 = value.trim().parse().ok()?;
+    if !(Score::MIN.value()..=Score::MAX.value()).contains(&value) {
+        return None;
+    }
+    let score = Score::new(value);
+    let separator = usize::from(chars.get(end + 1) == Some(&' '));
+    Some((Some(score), end + 1 + separator))
+}
+
+//The writer, lines are wrapped at 80 characters between moves
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+        for (name, value) in &self.headers {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        for (index, record_move) in self.moves.iter().enumerate() {
+            let mut token = String::new();
+            if index % 2 == 0 {
+                token += &format!("{}. ", index / 2 + 1);
+            }
+            token.push(notation::column_to_digit(record_move.column));
+            let score = record_move.score.map(|score| format!("[%score {}]", score));
+            let comment = record_move
+                .comment
+                .as_deref()
+                .filter(|comment| !comment.is_empty())
+                .map(|comment| {
+                    let escaped = comment.replace('\\', "\\\\").replace('}', "\\}");
+                    //Not to be read as a score
+                    if score.is_none() && escaped.starts_with('[') {
+                        format!("\\{}", escaped)
+                    } else {
+                        escaped
+                    }
+                });
+            let annotation: Vec<&str> = score
+                .iter()
+                .map(String::as_str)
+                .chain(comment.as_deref())
+                .collect();
+            if !annotation.is_empty() {
+                token += &format!(" {{{}}}", annotation.join(" "));
+            }
+            tokens.push(token);
+        }
+        tokens.push(self.result.to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.chars().count() > 80 {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.chars().count();
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let game = Game::from_string("4444445555553").unwrap();
+        let mut record = GameRecord::from_game(&game);
+        record.set_header("White", "Alice \"the solver\"").unwrap();
+        record.set_header("Black", "Bob").unwrap();
+        record.set_header("Date", "2024.05.01").unwrap();
+        record.moves[2].score = Some(Score::new(-3));
+        record.moves[2].comment = Some("Too slow".to_string());
+        record.moves[5].score = Some(Score::DRAW);
+        record.moves[7].comment = Some("Only move".to_string());
+        record.moves[9].comment = Some("Closes {the} column \\ threat".to_string());
+        record.result = GameResult::BlackWins; //White resigned
+
+        let text = record.to_string();
+        assert!(text.starts_with("[White \"Alice \\\"the solver\\\"\"]\n[Black \"Bob\"]\n"));
+        assert!(text.contains("[Result \"0-1\"]\n\n1. 4 4 2. 4 {[%score -3] Too slow} 4"));
+        assert!(text.lines().all(|line| line.chars().count() <= 80));
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.header("White"), Some("Alice \"the solver\""));
+        assert_eq!(parsed.to_game().unwrap(), game);
+
+        //Headers the parser would reject
+        for name in ["Bad name", "", "Result", "Black]"] {
+            assert!(matches!(
+                record.set_header(name, "x"),
+                Err(Error::InvalidHeaderName(invalid)) if invalid == name
+            ));
+        }
+        assert!(matches!(
+            record.set_header("Size", "8x7"),
+            Err(Error::UnsupportedSize(_))
+        ));
+        assert_eq!(GameRecord::parse(&record.to_string()).unwrap(), record);
+
+        //Comments are kept as written, and an empty comment is no comment
+        record.moves[3].comment = Some("[%score 3] fake".to_string());
+        record.moves[4].comment = Some(" padded ".to_string());
+        record.moves[5].comment = Some(" padded ".to_string());
+        let parsed = GameRecord::parse(&record.to_string()).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.moves[3].score, None);
+        record.moves[6].comment = Some(String::new());
+        let parsed = GameRecord::parse(&record.to_string()).unwrap();
+        assert_eq!(parsed.moves[6].comment, None);
+        record.moves[6].comment = None;
+        assert_eq!(parsed, record);
+
+        let finished = GameRecord::from_game(&Game::from_string("4455667").unwrap());
+        assert_eq!(finished.result, GameResult::WhiteWins);
+        assert_eq!(GameRecord::parse(&finished.to_string()).unwrap(), finished);
+    }
+
+    #[test]
+    fn parse() {
+        let record = GameRecord::parse("[Event \"Test\"]\n4453 {Opening} 1. 6 {[%score 5]} 2 *").unwrap();
+        assert_eq!(record.header("Event"), Some("Test"));
+        assert_eq!(record.columns(), vec![3, 3, 4, 2, 5, 1]);
+        assert_eq!(record.moves[3].comment.as_deref(), Some("Opening"));
+        assert_eq!(record.moves[4].score, Some(Score::new(5)));
+        assert_eq!(record.moves[4].comment, None);
+        assert_eq!(record.result, GameResult::Unfinished);
+        //The result comes from the board when it is not written
+        assert_eq!(GameRecord::parse("4455667").unwrap().result, GameResult::WhiteWins);
+
+        assert!(matches!(
+            GameRecord::parse("[White Alice]"),
+            Err(Error::InvalidHeader { index: 0 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("44 {Unclosed"),
+            Err(Error::UnclosedComment { index: 3 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("44 {Escaped \\}"),
+            Err(Error::UnclosedComment { index: 3 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("44 {[%score x]}"),
+            Err(Error::InvalidScore { index: 3 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("4 {[%score 999]}"),
+            Err(Error::InvalidScore { index: 2 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("1. 4 8"),
+            Err(Error::InvalidCharacter { index: 5, ch: '8' })
+        ));
+        assert!(matches!(
+            GameRecord::parse("4444444"),
+            Err(Error::IllegalMove { index: 6, cause }) if matches!(*cause, Error::ColumnFull { column: 3 })
+        ));
+        assert!(matches!(
+            GameRecord::parse("[Result \"1-0\"]\n44 0-1"),
+            Err(Error::ResultMismatch)
+        ));
+        assert!(matches!(
+            GameRecord::parse("4455667 0-1"),
+            Err(Error::ResultMismatch)
+        ));
+        assert!(matches!(
+            GameRecord::parse("[Size \"9x7\"]"),
+            Err(Error::UnsupportedSize(_))
+        ));
+        assert!(matches!(
+            GameRecord::parse("44 * 4"),
+            Err(Error::InvalidCharacter { index: 5, ch: '4' })
+        ));
+    }
+}
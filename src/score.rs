@@ -0,0 +1,179 @@
+use crate::board::{Board, Player};
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Neg;
+
+//Result of perfect play for the player to play
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+//A solver score, for the player to play (Pascal Pons' convention): positive for a win, negative
+//for a loss and 0 for a draw. A winner playing its n-th disc to win scores SQUARES / 2 + 1 - n, so
+//a higher score is always better, and the time of the win depends on the position (see
+//moves_to_end)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Score(i32);
+
+impl Score {
+    pub const DRAW: Score = Score(0);
+    //A win with the winner's 4th disc, the soonest possible
+    pub const MAX: Score = Score(Board::SQUARES as i32 / 2 - 3);
+    pub const MIN: Score = Score(-Self::MAX.0);
+
+    //Clamped to the possible scores
+    pub fn new(value: i32) -> Self {
+        Self(value.clamp(Self::MIN.0, Self::MAX.0))
+    }
+
+    //Score of the player to play when it wins with its next move
+    pub fn winning_move(board: Board) -> Self {
+        Self((Board::SQUARES as i32 + 1 - board.filled_squares() as i32) / 2)
+    }
+
+    //Final score of a finished game for the player to play: a loss if the last move won, even when
+    //it filled the board
+    pub fn game_over(board: Board) -> Self {
+        if board.is_victory() {
+            let winner_discs = board.filled_squares().div_ceil(2);
+            Self(-((Board::SQUARES / 2 + 1 - winner_discs) as i32))
+        } else {
+            Self::DRAW
+        }
+    }
+
+    pub fn value(self) -> i32 {
+        self.0
+    }
+
+    pub fn outcome(self) -> Outcome {
+        match self.0.signum() {
+            1 => Outcome::Win,
+            0 => Outcome::Draw,
+            _ => Outcome::Loss,
+        }
+    }
+
+    //Number of discs played by the winner when the game ends, None for a draw
+    pub fn winner_discs(self) -> Option<u32> {
+        match self.outcome() {
+            Outcome::Draw => None,
+            _ => Some((Board::SQUARES / 2 + 1).saturating_sub(self.0.unsigned_abs())),
+        }
+    }
+
+    //Discs played by both players until the end of the game, the board being the scored position.
+    //0 for a win or loss sooner than the board allows
+    pub fn moves_to_end(self, board: Board) -> u32 {
+        let filled = board.filled_squares();
+        match (self.outcome(), self.winner_discs()) {
+            (Outcome::Win, Some(discs)) => (2 * discs).saturating_sub(2 * (filled / 2) + 1),
+            (Outcome::Loss, Some(discs)) => 2 * discs.saturating_sub(filled.div_ceil(2)),
+            _ => Board::SQUARES - filled,
+        }
+    }
+
+    pub fn winner(self, board: Board) -> Option<Player> {
+        match self.outcome() {
+            Outcome::Win => Some(board.player_to_play()),
+            Outcome::Draw => None,
+            Outcome::Loss => Some(board.player_to_play().opponent()),
+        }
+    }
+
+    //Displays as "White wins in 7" or "Draw"
+    pub fn describe(self, board: Board) -> ScoreDescription {
+        ScoreDescription { score: self, board }
+    }
+}
+
+//From the point of view of the other player
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score(-self.0)
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub struct ScoreDescription {
+    score: Score,
+    board: Board,
+}
+
+impl fmt::Display for ScoreDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.score.winner(self.board) {
+            Some(player) => write!(
+                f,
+                "{} wins in {}",
+                player,
+                self.score.moves_to_end(self.board)
+            ),
+            None => write!(f, "Draw"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_positions;
+
+    #[test]
+    fn interpretation() {
+        //Black to play loses: White plays its 21st disc, the 41st of the game
+        let board = Board::from_string("2252576253462244111563365343671351441").unwrap();
+        let score = Score::new(-1);
+        assert_eq!(score.outcome(), Outcome::Loss);
+        assert_eq!(score.winner(board), Some(Player::White));
+        assert_eq!(score.winner_discs(), Some(21));
+        assert_eq!(score.moves_to_end(board), 4);
+        assert_eq!(score.describe(board).to_string(), "White wins in 4");
+
+        //White to play wins at once
+        let board = Board::from_string("445566").unwrap();
+        let score = Score::new(18);
+        assert_eq!(score.moves_to_end(board), 1);
+        assert_eq!(score.describe(board).to_string(), "White wins in 1");
+        assert_eq!((-score).winner(board), Some(Player::Black));
+
+        assert_eq!(Score::DRAW.outcome(), Outcome::Draw);
+        assert_eq!(Score::DRAW.moves_to_end(board), Board::SQUARES - 6);
+        assert_eq!(Score::DRAW.describe(board).to_string(), "Draw");
+        assert!(Score::new(3) > Score::new(1) && Score::new(1) > Score::DRAW);
+        assert_eq!(Score::new(-2).to_string(), "-2");
+    }
+
+    #[test]
+    fn impossible_scores() {
+        assert_eq!(Score::new(100), Score::MAX);
+        assert_eq!(Score::new(-100), Score::MIN);
+        assert_eq!(Score::new(100).winner_discs(), Some(4));
+        //Too soon for a board with 10 discs
+        let board = Board::from_string("4444455555").unwrap();
+        assert_eq!(Score::MAX.moves_to_end(board), 0);
+        assert_eq!(Score::MIN.moves_to_end(board), 0);
+    }
+
+    #[test]
+    fn agrees_with_play() {
+        //The moves to the end match the solved principal variation
+        let mut solver = crate::Solver::new();
+        for (board, score) in test_positions(include_str!("./test_sets/Test_L3_R1")).take(30) {
+            let variation = solver.principal_variation(board);
+            assert_eq!(variation.len() as u32, score.moves_to_end(board), "{}", board);
+        }
+    }
+}
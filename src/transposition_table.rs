@@ -1,72 +1,228 @@
+use crate::error::Error;
+use crate::score::Score;
 use crate::Board;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum TranspositionTableValue {
-    UpperBound(i32),
-    LowerBound(i32),
+    UpperBound(Score),
+    LowerBound(Score),
+    Exact(Score),
 }
 
 #[derive(Copy, Clone)]
-struct Entry(u64);
+struct Entry(u64); //Key, best move (3 bits, 7 if none), score (8 bits), kind (2 bits)
 
 impl Entry {
-    pub fn new(key: u64, value: TranspositionTableValue) -> Self {
+    const NO_MOVE: u64 = 7;
+    const EMPTY: Entry = Entry(u64::MAX); //The key part does not represent any board
+
+    pub fn new(key: u64, value: TranspositionTableValue, best_move: Option<u32>) -> Self {
         let value = match value {
-            TranspositionTableValue::UpperBound(score) => ((score as u8) as u64) << 1,
-            TranspositionTableValue::LowerBound(score) => (((score as u8) as u64) << 1) | 1,
+            TranspositionTableValue::UpperBound(score) => ((score.value() as u8) as u64) << 2,
+            TranspositionTableValue::LowerBound(score) => (((score.value() as u8) as u64) << 2) | 1,
+            TranspositionTableValue::Exact(score) => (((score.value() as u8) as u64) << 2) | 2,
         };
-        Self((key << 9) | value)
+        let best_move = best_move.map_or(Self::NO_MOVE, |column| column as u64);
+        Self((key << 13) | (best_move << 10) | value)
     }
 
     pub fn get_key(self) -> u64 {
-        self.0 >> 9
+        self.0 >> 13
     }
 
-    fn extract_score(&self) -> i32 {
-        let byte = ((self.0 >> 1) & 0xff) as u8;
-        (byte as i8) as i32
+    pub fn get_best_move(self) -> Option<u32> {
+        match (self.0 >> 10) & 7 {
+            Self::NO_MOVE => None,
+            column => Some(column as u32),
+        }
+    }
+
+    fn extract_score(&self) -> Score {
+        let byte = ((self.0 >> 2) & 0xff) as u8;
+        Score::new((byte as i8) as i32)
     }
 
     pub fn get_value(self) -> TranspositionTableValue {
-        if self.0 & 1 == 0 {
-            TranspositionTableValue::UpperBound(self.extract_score())
-        } else {
-            TranspositionTableValue::LowerBound(self.extract_score())
+        match self.0 & 3 {
+            0 => TranspositionTableValue::UpperBound(self.extract_score()),
+            1 => TranspositionTableValue::LowerBound(self.extract_score()),
+            _ => TranspositionTableValue::Exact(self.extract_score()),
         }
     }
 }
 
+//Shared between the threads of a batch: an entry is a single atomic word, whose key tells whether it
+//belongs to the board, so concurrent writes can only replace an entry with another valid one
 pub struct TranspositionTable {
-    table: Vec<Entry>,
+    table: Vec<AtomicU64>,
+    symmetric: bool, //A board and its mirror image share their entry
 }
 
 impl TranspositionTable {
-    const SIZE: usize = 16777259; //(64MB) This is a prime number to reduce collisions
+    const SIZE: usize = 16777259; //(128MB) This is a prime number to reduce collisions
+    const MAGIC: &'static [u8; 4] = b"C4TT";
+    const VERSION: u32 = 1; //To change with the entry encoding
+    const HEADER_SIZE: usize = 16;
+    const IO_CHUNK: usize = 1 << 16; //Entries read or written at once
 
-    pub fn new() -> Self {
+    pub fn new(symmetric: bool) -> Self {
+        Self::with_size(Self::SIZE, symmetric)
+    }
+
+    //Smaller tables are for tests, the size should be a prime number too
+    pub(crate) fn with_size(size: usize, symmetric: bool) -> Self {
         Self {
-            table: vec![
-                Entry::new(0xffffffffffffff, TranspositionTableValue::UpperBound(0));
-                Self::SIZE
-            ], //0xffffffffffffff does not represent any board
+            table: (0..size).map(|_| AtomicU64::new(Entry::EMPTY.0)).collect(),
+            symmetric,
         }
     }
 
-    fn index(key: u64) -> usize {
-        (key % (Self::SIZE as u64)) as usize
+    fn index(&self, key: u64) -> usize {
+        (key % (self.table.len() as u64)) as usize
     }
 
-    pub fn set(&mut self, board: Board, value: TranspositionTableValue) {
+    //The key, and whether the entry is stored for the mirror image of the board
+    fn key(&self, board: Board) -> (u64, bool) {
         let key = board.key();
-        self.table[Self::index(key)] = Entry::new(key, value);
+        if self.symmetric {
+            let symmetric_key = Board::symmetric_key(key);
+            (key.min(symmetric_key), symmetric_key < key)
+        } else {
+            (key, false)
+        }
     }
 
-    pub fn get(&self, board: Board) -> Option<TranspositionTableValue> {
-        let key = board.key();
-        let entry = self.table[Self::index(key)];
+    fn mirror_move(best_move: Option<u32>, mirrored: bool) -> Option<u32> {
+        if mirrored {
+            best_move.map(|column| Board::WIDTH - 1 - column)
+        } else {
+            best_move
+        }
+    }
+
+    pub fn set(&self, board: Board, value: TranspositionTableValue, best_move: Option<u32>) {
+        let (key, mirrored) = self.key(board);
+        let best_move = Self::mirror_move(best_move, mirrored);
+        let entry = Entry::new(key, value, best_move);
+        self.table[self.index(key)].store(entry.0, Ordering::Relaxed);
+    }
+
+    //Dump: the magic bytes, the version and the number of entries, then the entries, all little
+    //endian. The entries don't depend on symmetric, so a dump can be loaded in any table.
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let mut header = Vec::with_capacity(Self::HEADER_SIZE);
+        header.extend_from_slice(Self::MAGIC);
+        header.extend_from_slice(&Self::VERSION.to_le_bytes());
+        header.extend_from_slice(&(self.table.len() as u64).to_le_bytes());
+        writer.write_all(&header).map_err(Error::TableIo)?;
+        let mut bytes = Vec::with_capacity(8 * Self::IO_CHUNK);
+        for entries in self.table.chunks(Self::IO_CHUNK) {
+            bytes.clear();
+            for entry in entries {
+                bytes.extend_from_slice(&entry.load(Ordering::Relaxed).to_le_bytes());
+            }
+            writer.write_all(&bytes).map_err(Error::TableIo)?;
+        }
+        writer.flush().map_err(Error::TableIo)
+    }
+
+    //Replaces the entries with the ones of a dump, the table is unchanged on error
+    pub fn load<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        let mut header = [0; Self::HEADER_SIZE];
+        reader.read_exact(&mut header).map_err(Error::TableIo)?;
+        if &header[..4] != Self::MAGIC {
+            return Err(Error::InvalidTableHeader);
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != Self::VERSION {
+            return Err(Error::UnsupportedTableVersion { version });
+        }
+        let size = u64::from_le_bytes(header[8..].try_into().unwrap());
+        if size != self.table.len() as u64 {
+            return Err(Error::WrongTableSize { size });
+        }
+        let mut table = Vec::with_capacity(self.table.len());
+        let mut bytes = vec![0; 8 * Self::IO_CHUNK];
+        while table.len() < self.table.len() {
+            let count = Self::IO_CHUNK.min(self.table.len() - table.len());
+            reader.read_exact(&mut bytes[..8 * count]).map_err(Error::TableIo)?;
+            for entry in bytes[..8 * count].chunks_exact(8) {
+                table.push(AtomicU64::new(u64::from_le_bytes(entry.try_into().unwrap())));
+            }
+        }
+        self.table = table;
+        Ok(())
+    }
+
+    pub fn get(&self, board: Board) -> Option<(TranspositionTableValue, Option<u32>)> {
+        let (key, mirrored) = self.key(board);
+        let entry = Entry(self.table[self.index(key)].load(Ordering::Relaxed));
         if entry.get_key() == key {
-            Some(entry.get_value())
+            Some((entry.get_value(), Self::mirror_move(entry.get_best_move(), mirrored)))
         } else {
             None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_encoding() {
+        let key = Board::from_string("4453").unwrap().key();
+        let values = [
+            TranspositionTableValue::UpperBound(Score::new(-18)),
+            TranspositionTableValue::LowerBound(Score::new(7)),
+            TranspositionTableValue::Exact(Score::new(0)),
+            TranspositionTableValue::Exact(Score::new(-1)),
+        ];
+        for value in values {
+            for best_move in [None, Some(0), Some(Board::WIDTH - 1)] {
+                let entry = Entry::new(key, value, best_move);
+                assert_eq!(entry.get_key(), key);
+                assert_eq!(entry.get_value(), value);
+                assert_eq!(entry.get_best_move(), best_move);
+            }
+        }
+        assert_ne!(Entry::EMPTY.get_key(), key);
+    }
+
+    #[test]
+    fn dump() {
+        const SIZE: usize = 1009;
+        let table = TranspositionTable::with_size(SIZE, true);
+        let board = Board::from_string("4453").unwrap();
+        table.set(board, TranspositionTableValue::Exact(Score::new(3)), Some(1));
+        let mut dump = Vec::new();
+        table.save(&mut dump).unwrap();
+        assert_eq!(dump.len(), TranspositionTable::HEADER_SIZE + 8 * SIZE);
+
+        let mut loaded = TranspositionTable::with_size(SIZE, true);
+        assert!(loaded.get(board).is_none());
+        loaded.load(dump.as_slice()).unwrap();
+        assert_eq!(loaded.get(board), table.get(board));
+        assert_eq!(loaded.get(board.symmetric_board()), table.get(board.symmetric_board()));
+
+        //The loaded entries are kept on error, the header is checked before reading any entry
+        let header = |index: usize, byte: u8| {
+            let mut header = dump[..TranspositionTable::HEADER_SIZE].to_vec();
+            header[index] = byte;
+            header
+        };
+        assert!(matches!(loaded.load(header(0, b'X').as_slice()), Err(Error::InvalidTableHeader)));
+        assert!(matches!(
+            loaded.load(header(4, 2).as_slice()),
+            Err(Error::UnsupportedTableVersion { version: 2 })
+        ));
+        assert!(matches!(
+            TranspositionTable::with_size(1013, true).load(dump.as_slice()),
+            Err(Error::WrongTableSize { size }) if size == SIZE as u64
+        ));
+        assert!(matches!(loaded.load(&dump[..dump.len() - 1]), Err(Error::TableIo(_))));
+        assert_eq!(loaded.get(board), table.get(board));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::board::{Board, Player};
+
+//The follow-up rules below rely on every column having an even height
+const _: () = assert!(Board::HEIGHT.is_multiple_of(2));
+
+//What the static analysis proves for the player to play
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    AtLeastDraw,
+    AtMostDraw,
+    Loss,
+}
+
+//Allis's rules with White to play: Black answers in the same column (claimeven), and pairs up the
+//columns with an odd number of empty squares, answering a move in one of them in the other one
+//(baseinverse). Black then gets every empty square on an even row, except one of the two bottom
+//squares of each pair. If every White four needs a Black square, White cannot win, and Black wins
+//if one of its fours only needs squares it gets.
+fn follow_up(board: Board) -> Option<Outcome> {
+    debug_assert_eq!(board.player_to_play(), Player::White);
+    let empty = board.empty_squares();
+    let bottoms = board.playable_positions() & !Board::ODD_ROWS; //In the columns with odd empty squares
+    let white = board.player_squares() | (empty & Board::ODD_ROWS);
+    if Board::has_four(white) {
+        return None; //Whatever the pairing
+    }
+    if Board::has_four(white | bottoms) {
+        let mut columns = Vec::new();
+        let mut remaining = bottoms;
+        while remaining != 0 {
+            columns.push(remaining & remaining.wrapping_neg());
+            remaining &= remaining - 1;
+        }
+        if !some_pairing_refutes(white, &columns, &mut Vec::new()) {
+            return None;
+        }
+    }
+    let black = board.opponent_squares() | (empty & !Board::ODD_ROWS & !bottoms);
+    if Board::has_four(black) {
+        Some(Outcome::Loss)
+    } else {
+        Some(Outcome::AtMostDraw)
+    }
+}
+
+//Whether White has no four whichever bottom square of each pair it takes
+fn refutes(white: u64, pairs: &[(u64, u64)]) -> bool {
+    match pairs.split_first() {
+        None => !Board::has_four(white),
+        Some((&(first, second), rest)) => {
+            refutes(white | first, rest) && refutes(white | second, rest)
+        }
+    }
+}
+
+fn some_pairing_refutes(white: u64, bottoms: &[u64], pairs: &mut Vec<(u64, u64)>) -> bool {
+    match bottoms.split_first() {
+        None => refutes(white, pairs),
+        Some((&first, rest)) => (0..rest.len()).any(|index| {
+            let mut others = rest.to_vec();
+            let second = others.remove(index);
+            pairs.push((first, second));
+            let found = some_pairing_refutes(white, &others, pairs);
+            pairs.pop();
+            found
+        }),
+    }
+}
+
+//Proves some outcomes without search. Only Black can take the follow-up role, so White's results
+//come from the position itself and Black's from the positions after each of its moves.
+pub fn evaluate(board: Board) -> Option<Outcome> {
+    if board.game_over() {
+        return None;
+    }
+    if board.has_winning_move() {
+        return Some(Outcome::Win);
+    }
+    match board.player_to_play() {
+        Player::White => follow_up(board),
+        Player::Black => {
+            let mut result = None;
+            let mut moves = board.non_losing_moves();
+            while moves != 0 {
+                let move_mask = moves & moves.wrapping_neg();
+                moves ^= move_mask;
+                match follow_up(unsafe { board.make_move_unchecked(move_mask) }) {
+                    Some(Outcome::Loss) => return Some(Outcome::Win),
+                    Some(_) => result = Some(Outcome::AtLeastDraw),
+                    None => {}
+                }
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_positions;
+
+    #[test]
+    fn follow_up_rules() {
+        //White to play and lose (score -1): Black's follow-up gets it a four
+        let board = Board::from_string("6663152233464774324614").unwrap();
+        assert!(!board.has_winning_move());
+        assert_eq!(evaluate(board), Some(Outcome::Loss));
+        //White to play and lose (score -5), though the follow-up only proves White can't win
+        let board = Board::from_string("5444347311237447").unwrap();
+        assert_eq!(evaluate(board), Some(Outcome::AtMostDraw));
+        //Black to play and draw (score 0), following up after its move
+        let board = Board::from_string("34473157477544433").unwrap();
+        assert_eq!(evaluate(board), Some(Outcome::AtLeastDraw));
+        //Nothing can be said about the early game
+        assert_eq!(evaluate(Board::empty()), None);
+    }
+
+    #[test]
+    fn agrees_with_solver() {
+        let mut proven = 0;
+        let test_sets = [
+            include_str!("./test_sets/Test_L3_R1"),
+            include_str!("./test_sets/Test_L2_R1"),
+            include_str!("./test_sets/Test_L2_R2"),
+            include_str!("./test_sets/Test_L1_R1"),
+            include_str!("./test_sets/Test_L1_R2"),
+            include_str!("./test_sets/Test_L1_R3"),
+        ];
+        for (board, score) in test_sets.iter().flat_map(|test_set| test_positions(test_set)) {
+            let score = score.value();
+            let consistent = match evaluate(board) {
+                Some(Outcome::Win) => score > 0,
+                Some(Outcome::AtLeastDraw) => score >= 0,
+                Some(Outcome::AtMostDraw) => score <= 0,
+                Some(Outcome::Loss) => score < 0,
+                None => continue,
+            };
+            assert!(consistent, "{}", board);
+            proven += 1;
+        }
+        assert!(proven > 0);
+    }
+}